@@ -1,17 +1,22 @@
 extern crate visca_ip;
 use visca_ip::*;
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
 
 fn main() {
-  let mut cam = Camera::new("10.0.0.1").unwrap();
+  let mut cam = Camera::new("10.0.0.1:52381").unwrap();
 
   let xs: [u32; 3] = [0x0374A, 0, 0xFC8B6];
   let ys: [u16; 3] = [0, 0x374A, 0];
-  let speed: u8 = 10;
+  let speed = params::PanTiltSpeed::new(10).unwrap();
   let mut i = 0;
-  while let Ok(pos) = cam.set(commands::PanTilt::AbsolutePos(speed, xs[i], ys[i])) {
-    println!("Camera was moved to {pos:?}.");
-    i = (i + 1) % 2;
-    sleep(Duration::from_secs(2));
+  loop {
+    match cam.execute(commands::PanTilt::AbsolutePos(speed, xs[i], ys[i]), Duration::from_secs(10)) {
+      Ok(()) => println!("Camera was moved to ({:05X}, {:04X}).", xs[i], ys[i]),
+      Err(e) => {
+        println!("Move failed: {e}");
+        break;
+      }
+    }
+    i = (i + 1) % 3;
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,218 @@
+// Keyframed camera moves.
+//
+// An `Animation` is a list of keyframes, each a time from the start of the
+// move and a position, reached from the previous keyframe along an easing
+// curve. `Camera::play` first goes to the opening keyframe, then follows the
+// curve by reading the position every tick and streaming whatever pan/tilt
+// and zoom speeds will bring the camera onto the curve by the next tick, so
+// errors in the speed table are corrected as the move runs. Focus follows with
+// `Focus::Direct` and needs manual focus. The move ends on the last keyframe
+// with `PanTilt::AbsolutePos` and `Zoom::Direct`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::{Focus, PanTilt, Zoom};
+use crate::drive::{pan_tilt_command, zoom_command};
+use crate::motion::{self, Position, SpeedTable};
+use crate::params::PanTiltSpeed;
+use crate::state::MOVE_TIMEOUT;
+use crate::{Camera, Result, ViscaError};
+
+const TICK: Duration = Duration::from_millis(100);
+
+// Smallest change in focus position worth sending
+const FOCUS_STEP: f32 = 64.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Maps progress through a segment (0.0 - 1.0) onto the curve
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe {
+    pub time: Duration, // from the start of the animation
+    pub position: Position,
+    pub focus: Option<u16>, // left alone if None
+    pub easing: Easing,     // of the segment arriving at this keyframe
+}
+
+// Deserialized through `Animation::new`, so keyframes are always checked
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<Keyframe>", into = "Vec<Keyframe>"))]
+pub struct Animation {
+    keyframes: Vec<Keyframe>,
+}
+
+impl TryFrom<Vec<Keyframe>> for Animation {
+    type Error = ViscaError;
+
+    fn try_from(keyframes: Vec<Keyframe>) -> Result<Self> {
+        Animation::new(keyframes)
+    }
+}
+
+impl From<Animation> for Vec<Keyframe> {
+    fn from(animation: Animation) -> Self {
+        animation.keyframes
+    }
+}
+
+// A point on the curve, with pan and tilt in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub pan: f32,
+    pub tilt: f32,
+    pub zoom: f32,
+    pub focus: Option<f32>,
+}
+
+impl Animation {
+    /// Keyframes must be in time order, starting at time zero
+    pub fn new(keyframes: Vec<Keyframe>) -> Result<Self> {
+        let ordered = keyframes.windows(2).all(|w| w[0].time < w[1].time);
+        match keyframes.first() {
+            Some(first) if first.time.is_zero() && ordered => Ok(Animation { keyframes }),
+            _ => Err(ViscaError::ParameterOutOfRange("keyframes")),
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    /// Where the camera should be at `time`
+    pub fn sample(&self, time: Duration) -> Sample {
+        let index = self.keyframes.iter().position(|k| k.time > time);
+        let (a, b) = match index {
+            Some(i) if i > 0 => (&self.keyframes[i - 1], &self.keyframes[i]),
+            _ => {
+                let k = if index.is_some() { &self.keyframes[0] } else { &self.keyframes[self.keyframes.len() - 1] };
+                (k, k)
+            }
+        };
+        let span = (b.time - a.time).as_secs_f32();
+        let t = if span > 0.0 { b.easing.apply((time - a.time).as_secs_f32() / span) } else { 1.0 };
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        let (from, to) = (a.position, b.position);
+        Sample {
+            pan: lerp(motion::pan_degrees(from.pan), motion::pan_degrees(to.pan)),
+            tilt: lerp(motion::tilt_degrees(from.tilt), motion::tilt_degrees(to.tilt)),
+            zoom: lerp(from.zoom as f32, to.zoom as f32),
+            focus: match (a.focus, b.focus) {
+                (Some(x), Some(y)) => Some(lerp(x as f32, y as f32)),
+                (x, y) => y.or(x).map(|f| f as f32),
+            },
+        }
+    }
+}
+
+// Signed speed setting that covers `rate` units per second, 0 if slower than
+// half the slowest speed. `rates` is in the order of the speed settings.
+fn speed_for(rates: &[f32], rate: f32, speed: impl Fn(f32) -> u8) -> i8 {
+    if rate.is_nan() || rate.abs() < rates[0] / 2.0 {
+        return 0;
+    }
+    let speed = speed(rate.abs()) as i8;
+    if rate < 0.0 { -speed } else { speed }
+}
+
+impl Camera {
+    /// Moves to the first keyframe, then drives the camera along `animation`,
+    /// returning once it has settled on the last keyframe
+    pub fn play(&mut self, table: &SpeedTable, animation: &Animation) -> Result<()> {
+        let first = &animation.keyframes[0];
+        self.go_to(first.position, PanTiltSpeed::MAX, first.focus)?;
+        if let Err(e) = self.drive_animation(table, animation) {
+            // Don't leave the camera running if a position read or command failed
+            self.stop_moving();
+            return Err(e);
+        }
+
+        // Close whatever gap is left over about one tick
+        let last = &animation.keyframes[animation.keyframes.len() - 1];
+        let now = self.position()?;
+        let gap = (motion::pan_degrees(last.position.pan) - motion::pan_degrees(now.pan))
+            .abs()
+            .max((motion::tilt_degrees(last.position.tilt) - motion::tilt_degrees(now.tilt)).abs());
+        let speed = table.pan_speed(gap / TICK.as_secs_f32()).max(table.tilt_speed(gap / TICK.as_secs_f32()));
+        self.go_to(last.position, speed, last.focus)
+    }
+
+    // The continuous part of `play`, from the first keyframe until stopped
+    // at the end of the animation
+    fn drive_animation(&mut self, table: &SpeedTable, animation: &Animation) -> Result<()> {
+        let first = &animation.keyframes[0];
+        let start = Instant::now();
+        let mut sent = (0i8, 0i8, 0i8);
+        let mut focus = first.focus.map(|f| f as f32);
+        while start.elapsed() < animation.duration() {
+            let now = self.position()?;
+            let target = animation.sample(start.elapsed() + TICK);
+            let seconds = TICK.as_secs_f32();
+
+            // Positive pan is to the left in positions but to the right in
+            // `pan_tilt_command`; zoom speeds there are offset by one
+            let pan = (target.pan - motion::pan_degrees(now.pan)) / seconds;
+            let tilt = (target.tilt - motion::tilt_degrees(now.tilt)) / seconds;
+            let zoom = (target.zoom - now.zoom as f32) / seconds;
+            let speeds = (
+                -speed_for(&table.pan, pan, |r| table.pan_speed(r).value()),
+                speed_for(&table.tilt, tilt, |r| table.tilt_speed(r).value()),
+                speed_for(&table.zoom, zoom, |r| table.zoom_speed(r).value() + 1),
+            );
+
+            if (speeds.0, speeds.1) != (sent.0, sent.1) {
+                self.set(pan_tilt_command(speeds.0, speeds.1)).map_err(ViscaError::CameraConnectionError)?;
+            }
+            if speeds.2 != sent.2 {
+                self.set(zoom_command(speeds.2)).map_err(ViscaError::CameraConnectionError)?;
+            }
+            sent = speeds;
+
+            if let Some(f) = target.focus {
+                if focus.is_none_or(|last| (f - last).abs() >= FOCUS_STEP) {
+                    self.set(Focus::Direct(f.round() as u16)).map_err(ViscaError::CameraConnectionError)?;
+                    focus = Some(f);
+                }
+            }
+            thread::sleep(TICK);
+        }
+
+        self.set(pan_tilt_command(0, 0)).map_err(ViscaError::CameraConnectionError)?;
+        self.set(zoom_command(0)).map_err(ViscaError::CameraConnectionError)
+    }
+
+    fn go_to(&mut self, position: Position, speed: PanTiltSpeed, focus: Option<u16>) -> Result<()> {
+        self.execute(PanTilt::AbsolutePos(speed, position.pan, position.tilt), MOVE_TIMEOUT)?;
+        self.execute(Zoom::Direct(position.zoom), MOVE_TIMEOUT)?;
+        if let Some(focus) = focus {
+            self.execute(Focus::Direct(focus), MOVE_TIMEOUT)?;
+        }
+        Ok(())
+    }
+}
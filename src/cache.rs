@@ -0,0 +1,245 @@
+// Local cache of what the camera last told us, or was last told.
+//
+// Replies to inquiries are kept by inquiry. A command that completes updates
+// the reply of the inquiry that reads the setting it changes, where the
+// command carries the value in the same form as the reply (mode and direct
+// value commands such as `Exposure::ModeManual`, `Colour::WBIndoor` or
+// `Zoom::Direct`). Any command first drops the replies it may have changed:
+// its own setting, both the step (`04 0B`) and direct (`04 4B`) forms, and
+// settings it is known to move, e.g. the iris with the exposure mode. Pan/tilt
+// commands drop the position, memory recalls and the extended `7E` commands
+// everything. Commands sent with `set` get no completion, so they only drop
+// replies.
+//
+// Entries older than `CacheConfig::max_age` are stale: still reported by
+// `Camera::cached` but never used to answer `inquire`. Values the camera
+// changes by itself (pan/tilt, zoom and focus position, and what the auto
+// exposure and white balance adjust) are kept for `Camera::cached` too, but
+// `inquire` always asks the camera for them, so position reads made while
+// driving or fencing are never frozen.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::state::CameraState;
+use crate::{Camera, ViscaInquiry};
+
+// Settings that change others in the camera category (04): the exposure mode
+// the iris, gain and shutter, the white balance mode the red and blue gains,
+// and the focus mode the focus position
+const MOVES: [(u8, &[u8]); 3] = [(0x39, &[0x4B, 0x4C, 0x4A]), (0x35, &[0x43, 0x44]), (0x38, &[0x48])];
+
+// Values the camera changes by itself in the camera category (04): zoom and
+// focus position, iris, gain, shutter, and the red and blue gains
+const VOLATILE: [u8; 7] = [0x47, 0x48, 0x4B, 0x4C, 0x4A, 0x43, 0x44];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub max_age: Duration,
+    pub answer_inquiries: bool, // answer `inquire` from fresh entries without asking the camera
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig { max_age: Duration::from_secs(10), answer_inquiries: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheSource {
+    Inquiry, // the camera's reply
+    Command, // derived from a completed command
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cached {
+    pub reply: Vec<u8>, // as `Camera::inquire` returns it
+    pub age: Duration,
+    pub stale: bool,
+    pub source: CacheSource,
+}
+
+#[derive(Debug)]
+struct Entry {
+    reply: Vec<u8>,
+    at: Instant,
+    source: CacheSource,
+}
+
+#[derive(Debug)]
+pub(crate) struct StateCache {
+    config: CacheConfig,
+    entries: HashMap<Vec<u8>, Entry>, // by inquiry bytes
+}
+
+impl StateCache {
+    fn new(config: CacheConfig) -> Self {
+        StateCache { config, entries: HashMap::new() }
+    }
+
+    fn get(&self, inquiry: &[u8]) -> Option<Cached> {
+        let entry = self.entries.get(inquiry)?;
+        let age = entry.at.elapsed();
+        Some(Cached { reply: entry.reply.clone(), age, stale: age > self.config.max_age, source: entry.source })
+    }
+
+    // A fresh reply to answer `inquire` with, if the cache is allowed to
+    pub fn answer(&self, inquiry: &[u8]) -> Option<Vec<u8>> {
+        if volatile(inquiry) {
+            return None;
+        }
+        let cached = self.get(inquiry).filter(|c| self.config.answer_inquiries && !c.stale)?;
+        Some(cached.reply)
+    }
+
+    pub fn inquired(&mut self, inquiry: Vec<u8>, reply: Vec<u8>) {
+        self.entries.insert(inquiry, Entry { reply, at: Instant::now(), source: CacheSource::Inquiry });
+    }
+
+    // Called for every command sent, before it is known to have worked
+    pub fn sent(&mut self, command: &[u8]) {
+        match command {
+            [_, 0x01, 0x04, 0x3F, ..] | [_, 0x01, 0x7E, ..] => self.entries.clear(),
+            [_, 0x01, 0x04, id, ..] => {
+                let moved = MOVES.iter().find(|(i, _)| i == id).map_or(&[][..], |(_, moved)| moved);
+                self.entries.retain(|inquiry, _| match inquiry[..] {
+                    [_, 0x09, 0x04, i, 0xFF] => i != *id && i != *id | 0x40 && !moved.contains(&i),
+                    _ => true,
+                });
+            }
+            [_, 0x01, category, ..] => self.entries.retain(|inquiry, _| inquiry.get(2) != Some(category)),
+            _ => {}
+        }
+    }
+
+    // Called when a command completes: `81 01 cc ii pp.. FF` sets what
+    // `81 09 cc ii FF` reads, answered with `90 50 pp.. FF`
+    pub fn completed(&mut self, command: &[u8]) {
+        self.sent(command);
+        let [address, 0x01, category, id, params @ .., 0xFF] = command else { return };
+        if params.is_empty() {
+            return;
+        }
+        let inquiry = vec![*address, 0x09, *category, *id, 0xFF];
+        let reply = [&[0x90, 0x50], params, &[0xFF]].concat();
+        if CameraState::valid_reply(&inquiry, &reply) {
+            self.entries.insert(inquiry, Entry { reply, at: Instant::now(), source: CacheSource::Command });
+        }
+    }
+}
+
+// Whether `inquiry` reads a value the cache must not answer for
+fn volatile(inquiry: &[u8]) -> bool {
+    match inquiry {
+        [_, 0x09, 0x06, 0x12, 0xFF] => true, // pan/tilt position
+        [_, 0x09, 0x04, id, 0xFF] => VOLATILE.contains(id),
+        _ => false,
+    }
+}
+
+impl Camera {
+    /// Starts keeping the camera's state locally, from inquiry replies and
+    /// completed commands. See `CacheConfig`.
+    pub fn enable_cache(&mut self, config: CacheConfig) {
+        self.cache = Some(StateCache::new(config));
+    }
+
+    pub fn disable_cache(&mut self) {
+        self.cache = None;
+    }
+
+    /// The cached reply to `inquiry`, if the cache is enabled and has one
+    pub fn cached(&self, inquiry: &impl ViscaInquiry) -> Option<Cached> {
+        self.cache.as_ref()?.get(&inquiry.bytes())
+    }
+
+    /// Forgets everything cached, e.g. after the camera was operated by
+    /// other means
+    pub fn clear_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.entries.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODE: [u8; 5] = [0x81, 0x09, 0x04, 0x39, 0xFF];
+    const IRIS: [u8; 5] = [0x81, 0x09, 0x04, 0x4B, 0xFF];
+    const WB_MODE: [u8; 5] = [0x81, 0x09, 0x04, 0x35, 0xFF];
+    const PAN_TILT: [u8; 5] = [0x81, 0x09, 0x06, 0x12, 0xFF];
+
+    fn filled() -> StateCache {
+        let mut cache = StateCache::new(CacheConfig { answer_inquiries: true, ..CacheConfig::default() });
+        cache.inquired(MODE.to_vec(), vec![0x90, 0x50, 0x03, 0xFF]);
+        cache.inquired(IRIS.to_vec(), vec![0x90, 0x50, 0x00, 0x00, 0x00, 0x0A, 0xFF]);
+        cache.inquired(WB_MODE.to_vec(), vec![0x90, 0x50, 0x00, 0xFF]);
+        cache.inquired(PAN_TILT.to_vec(), vec![0x90, 0x50, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF]);
+        cache
+    }
+
+    #[test]
+    fn sent_drops_setting_and_dependents() {
+        let mut cache = filled();
+        // Exposure mode full auto moves the iris
+        cache.sent(&[0x81, 0x01, 0x04, 0x39, 0x00, 0xFF]);
+        assert!(cache.get(&MODE).is_none());
+        assert!(cache.get(&IRIS).is_none());
+        assert!(cache.get(&WB_MODE).is_some());
+        assert!(cache.get(&PAN_TILT).is_some());
+
+        // Iris up drops the direct form
+        let mut cache = filled();
+        cache.sent(&[0x81, 0x01, 0x04, 0x0B, 0x02, 0xFF]);
+        assert!(cache.get(&IRIS).is_none());
+        assert!(cache.get(&MODE).is_some());
+    }
+
+    #[test]
+    fn sent_drops_category_or_everything() {
+        let mut cache = filled();
+        cache.sent(&[0x81, 0x01, 0x06, 0x01, 0x05, 0x05, 0x03, 0x03, 0xFF]);
+        assert!(cache.get(&PAN_TILT).is_none());
+        assert!(cache.get(&MODE).is_some());
+
+        // Memory recall
+        let mut cache = filled();
+        cache.sent(&[0x81, 0x01, 0x04, 0x3F, 0x02, 0x01, 0xFF]);
+        assert!(cache.entries.is_empty());
+
+        let mut cache = filled();
+        cache.sent(&[0x81, 0x01, 0x7E, 0x01, 0x5A, 0x00, 0xFF]);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn completed_derives_reply() {
+        let mut cache = filled();
+        cache.completed(&[0x81, 0x01, 0x04, 0x39, 0x03, 0xFF]);
+        let cached = cache.get(&MODE).unwrap();
+        assert_eq!(cached.reply, [0x90, 0x50, 0x03, 0xFF]);
+        assert_eq!(cached.source, CacheSource::Command);
+        assert_eq!(cache.answer(&MODE), Some(vec![0x90, 0x50, 0x03, 0xFF]));
+        // Still dropped what the mode change moves
+        assert!(cache.get(&IRIS).is_none());
+
+        cache.completed(&[0x81, 0x01, 0x04, 0x4B, 0x00, 0x00, 0x01, 0x02, 0xFF]);
+        assert_eq!(cache.get(&IRIS).unwrap().reply, [0x90, 0x50, 0x00, 0x00, 0x01, 0x02, 0xFF]);
+        // The camera adjusts the iris itself, so it is never answered from the cache
+        assert_eq!(cache.answer(&IRIS), None);
+    }
+
+    #[test]
+    fn completed_ignores_commands_without_a_matching_inquiry() {
+        let mut cache = filled();
+        cache.completed(&[0x81, 0x01, 0x04, 0x0B, 0x02, 0xFF]);
+        assert!(cache.get(&[0x81, 0x09, 0x04, 0x0B, 0xFF]).is_none());
+        assert!(cache.get(&IRIS).is_none());
+
+        // Out of range for the exposure mode
+        cache.completed(&[0x81, 0x01, 0x04, 0x39, 0x07, 0xFF]);
+        assert!(cache.get(&MODE).is_none());
+    }
+}
@@ -1,12 +1,10 @@
 #![allow(dead_code)]
 
 use crate::messages::{self as msg, ViscaCommand};
+use crate::params::*;
 
-/*
-* TODO: Unify on/off behaviour
-*/
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Exposure {
     ModeFullAuto,
     ModeManual,
@@ -16,41 +14,36 @@ pub enum Exposure {
     IrisReset,
     IrisUp,
     IrisDown,
-    IrisDirect(u8), // Iris Position 05 - 15
+    IrisDirect(Iris),
     GainReset,
     GainUp,
     GainDown,
-    GainDirect(u8),   // 00 (-3dB) - 0C (33dB)
-    GainLimit(u8),    // 4 (9dB) - 9 (24dB), FF (off)
-    GainPoint(u8),    // 2=on, 3=off
-    GainPointPos(u8), // 01 (0dB) - 09 (24dB)
+    GainDirect(Gain),
+    GainLimit(GainLimit), // GainLimit::OFF to disable
+    GainPoint(OnOff),
+    GainPointPos(GainPointPosition),
     ShutterReset,
     ShutterUp,
     ShutterDown,
-    ShutterDirect(u8), // Shutter Position 01 - 15
-    MaxShutter(u8),    // 03 - 15
-    MinShutter(u8),    // 03 - 15
-    AESpeed(u8),       // 01 - 30
-    ExpCompOnOff(u8),  // 2=on, 3=off
+    ShutterDirect(Shutter),
+    MaxShutter(LimitShutter),
+    MinShutter(LimitShutter),
+    AESpeed(AESpeed),
+    ExpCompOnOff(OnOff),
     ExpCompReset,
     ExpCompUp,
     ExpCompDown,
-    ExpCompDirect(u8), // 00 - 0E
-    BackLight(u8),     // 2=on, 3=off
-    SpotLight(u8),     // 2=on, 3=off
+    ExpCompDirect(ExpComp),
+    BackLight(OnOff),
+    SpotLight(OnOff),
     VisEnhanceOn,
     VisEnhanceOff,
-    VisEnhanceDirect(u8, u8, u8), /* p: Effect level:
-                                   *    0 (Dark) - 6 (Bright)
-                                   * q: Brightness compensation selection:
-                                   *    0 (Very dark), 1 (Dark), 2 (Standard), 3 (Bright)
-                                   * r: Compensation level:
-                                   *    0 (Low), 1 (Mid), 2 (High) */
+    VisEnhanceDirect(VisEnhanceLevel, BrightnessComp, CompLevel),
 
-    IrCutFilterOnOff(u8),              // 2=on (night), 3=off (day)
-    LowLightBasisBrightnessOnOff(u8),  // 2=on, 3=off
-    LowLightBasisBrightnessDirect(u8), // 4 - A
-    NDFilter(u8),                      // 0=off, 1=1/4, 2=1/16, 3=1/64
+    IrCutFilterOnOff(OnOff), // On (night), Off (day)
+    LowLightBasisBrightnessOnOff(OnOff),
+    LowLightBasisBrightnessDirect(LowLightBasisBrightness),
+    NDFilter(NDFilter),
 }
 
 impl msg::ViscaMessage for Exposure {
@@ -73,8 +66,8 @@ impl msg::ViscaMessage for Exposure {
                     0x4B,
                     0x00,
                     0x00,
-                    msg::u8top(*val),
-                    msg::u8bot(*val),
+                    msg::u8top(val.value()),
+                    msg::u8bot(val.value()),
                     0xFF,
                 ]
             }
@@ -90,14 +83,14 @@ impl msg::ViscaMessage for Exposure {
                     0x4C,
                     0x00,
                     0x00,
-                    msg::u8top(*val),
-                    msg::u8bot(*val),
+                    msg::u8top(val.value()),
+                    msg::u8bot(val.value()),
                     0xFF,
                 ]
             }
-            Self::GainLimit(val) => vec![0x81, 0x01, 0x04, 0x2C, *val, 0xFF],
-            Self::GainPoint(val) => vec![0x81, 0x01, 0x05, 0x0C, *val, 0xFF],
-            Self::GainPointPos(val) => vec![0x81, 0x01, 0x05, 0x4C, msg::u8top(*val), msg::u8bot(*val), 0xFF],
+            Self::GainLimit(val) => vec![0x81, 0x01, 0x04, 0x2C, val.value(), 0xFF],
+            Self::GainPoint(val) => vec![0x81, 0x01, 0x05, 0x0C, val.value(), 0xFF],
+            Self::GainPointPos(val) => vec![0x81, 0x01, 0x05, 0x4C, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
 
             Self::ShutterReset => vec![0x81, 0x01, 0x04, 0x0A, 0x00, 0xFF],
             Self::ShutterUp => vec![0x81, 0x01, 0x04, 0x0A, 0x02, 0xFF],
@@ -110,21 +103,21 @@ impl msg::ViscaMessage for Exposure {
                     0x4A,
                     0x00,
                     0x00,
-                    msg::u8top(*val),
-                    msg::u8bot(*val),
+                    msg::u8top(val.value()),
+                    msg::u8bot(val.value()),
                     0xFF,
                 ]
             }
             Self::MaxShutter(val) => {
-                vec![0x81, 0x01, 0x05, 0x2A, 0x00, msg::u8top(*val), msg::u8bot(*val), 0xFF]
+                vec![0x81, 0x01, 0x05, 0x2A, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
             }
             Self::MinShutter(val) => {
-                vec![0x81, 0x01, 0x05, 0x2A, 0x01, msg::u8top(*val), msg::u8bot(*val), 0xFF]
+                vec![0x81, 0x01, 0x05, 0x2A, 0x01, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
             }
 
-            Self::AESpeed(val) => vec![0x81, 0x01, 0x04, 0x5D, *val, 0xFF],
+            Self::AESpeed(val) => vec![0x81, 0x01, 0x04, 0x5D, val.value(), 0xFF],
 
-            Self::ExpCompOnOff(val) => vec![0x81, 0x01, 0x04, 0x3E, *val, 0xFF],
+            Self::ExpCompOnOff(val) => vec![0x81, 0x01, 0x04, 0x3E, val.value(), 0xFF],
             Self::ExpCompReset => vec![0x81, 0x01, 0x04, 0x0E, 0x00, 0xFF],
             Self::ExpCompUp => vec![0x81, 0x01, 0x04, 0x0E, 0x02, 0xFF],
             Self::ExpCompDown => vec![0x81, 0x01, 0x04, 0x0E, 0x03, 0xFF],
@@ -136,29 +129,29 @@ impl msg::ViscaMessage for Exposure {
                     0x4E,
                     0x00,
                     0x00,
-                    msg::u8top(*val),
-                    msg::u8bot(*val),
+                    msg::u8top(val.value()),
+                    msg::u8bot(val.value()),
                     0xFF,
                 ]
             }
 
-            Self::BackLight(val) => vec![0x81, 0x01, 0x04, 0x33, *val, 0xFF],
+            Self::BackLight(val) => vec![0x81, 0x01, 0x04, 0x33, val.value(), 0xFF],
 
-            Self::SpotLight(val) => vec![0x81, 0x01, 0x04, 0x3A, *val, 0xFF],
+            Self::SpotLight(val) => vec![0x81, 0x01, 0x04, 0x3A, val.value(), 0xFF],
 
             // not standard! on = 06, off = 03
             Self::VisEnhanceOn => vec![0x81, 0x01, 0x04, 0x3D, 0x06, 0xFF],
             Self::VisEnhanceOff => vec![0x81, 0x01, 0x04, 0x3D, 0x03, 0xFF],
             Self::VisEnhanceDirect(p, q, r) => vec![
-                0x81, 0x01, 0x04, 0x2D, 0x00, *p, *q, *r, 0x00, 0x00, 0x00, 0x00, 0xFF,
+                0x81, 0x01, 0x04, 0x2D, 0x00, p.value(), q.value(), r.value(), 0x00, 0x00, 0x00, 0x00, 0xFF,
             ],
 
-            Self::IrCutFilterOnOff(val) => vec![0x81, 0x01, 0x04, 0x01, *val, 0xFF],
+            Self::IrCutFilterOnOff(val) => vec![0x81, 0x01, 0x04, 0x01, val.value(), 0xFF],
 
-            Self::LowLightBasisBrightnessOnOff(val) => vec![0x81, 0x01, 0x05, 0x39, *val, 0xFF],
-            Self::LowLightBasisBrightnessDirect(val) => vec![0x81, 0x01, 0x05, 0x49, *val, 0xFF],
+            Self::LowLightBasisBrightnessOnOff(val) => vec![0x81, 0x01, 0x05, 0x39, val.value(), 0xFF],
+            Self::LowLightBasisBrightnessDirect(val) => vec![0x81, 0x01, 0x05, 0x49, val.value(), 0xFF],
 
-            Self::NDFilter(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x53, *val, 0xFF],
+            Self::NDFilter(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x53, val.value(), 0xFF],
         }
     }
     fn msg_type(&self) -> msg::MessageType {
@@ -167,12 +160,13 @@ impl msg::ViscaMessage for Exposure {
 }
 
 impl ViscaCommand for Exposure {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Colour {
     WBAuto1,
     WBIndoor,
@@ -189,29 +183,27 @@ pub enum Colour {
     BGainUp,
     BGainDown,
     BGainDirect(u8), // 00 (-128) - 80 (0) - FF (128)
-    Speed(u8),       // 1 (slow) - 5 (fast)
+    Speed(WBSpeed),
     OffsetReset,
     OffsetUp,
     OffsetDown,
-    OffsetDirect(u8),   // 0 (-7) - 7 (0) - E (+7)
-    ChromaSuppress(u8), // 0 (off), 1 (weak) - 3 (strong)
-    MatrixSelect(u8),   /* Matrix Setting (2=STD, 3=OFF, 4=HIGH SAT,
-                         * 5=FL LIGHT, 6=MOVIE, 7=STILL,
-                         * 8=CINEMA, 9=PRO, A=ITU709, B=B/W) */
+    OffsetDirect(WBOffset),
+    ChromaSuppress(ChromaSuppress),
+    MatrixSelect(Matrix),
     LevelReset,
     LevelUp,
     LevelDown,
-    LevelDirect(u8), // 0 (0) - E (14)
+    LevelDirect(ColourLevel),
     PhaseReset,
     PhaseUp,
     PhaseDown,
-    PhaseDirect(u8), // 0 (-14 degrees) - E (+14 degrees)
-    RG(u8),          //00 (–99) - 63 (00) - C6 (+99)
-    RB(u8),          //00 (–99) - 63 (00) - C6 (+99)
-    GR(u8),          //00 (–99) - 63 (00) - C6 (+99)
-    GB(u8),          //00 (–99) - 63 (00) - C6 (+99)
-    BR(u8),          //00 (–99) - 63 (00) - C6 (+99)
-    BG(u8),          //00 (–99) - 63 (00) - C6 (+99)
+    PhaseDirect(ColourPhase),
+    RG(MatrixCoefficient),
+    RB(MatrixCoefficient),
+    GR(MatrixCoefficient),
+    GB(MatrixCoefficient),
+    BR(MatrixCoefficient),
+    BG(MatrixCoefficient),
 }
 
 impl msg::ViscaMessage for Colour {
@@ -257,32 +249,32 @@ impl msg::ViscaMessage for Colour {
                     0xFF,
                 ]
             }
-            Self::Speed(val) => vec![0x81, 0x01, 0x04, 0x56, *val, 0xFF],
+            Self::Speed(val) => vec![0x81, 0x01, 0x04, 0x56, val.value(), 0xFF],
 
             Self::OffsetReset => vec![0x81, 0x01, 0x7E, 0x01, 0x2E, 0x00, 0x00, 0xFF],
             Self::OffsetUp => vec![0x81, 0x01, 0x7E, 0x01, 0x2E, 0x00, 0x02, 0xFF],
             Self::OffsetDown => vec![0x81, 0x01, 0x7E, 0x01, 0x2E, 0x00, 0x03, 0xFF],
-            Self::OffsetDirect(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x2E, 0x01, *val, 0xFF],
+            Self::OffsetDirect(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x2E, 0x01, val.value(), 0xFF],
 
-            Self::ChromaSuppress(val) => vec![0x81, 0x01, 0x04, 0x5F, *val, 0xFF],
-            Self::MatrixSelect(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x3D, *val, 0xFF],
+            Self::ChromaSuppress(val) => vec![0x81, 0x01, 0x04, 0x5F, val.value(), 0xFF],
+            Self::MatrixSelect(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x3D, val.value(), 0xFF],
 
             Self::LevelReset => vec![0x81, 0x01, 0x04, 0x09, 0x00, 0xFF],
             Self::LevelUp => vec![0x81, 0x01, 0x04, 0x09, 0x02, 0xFF],
             Self::LevelDown => vec![0x81, 0x01, 0x04, 0x09, 0x03, 0xFF],
-            Self::LevelDirect(val) => vec![0x81, 0x01, 0x04, 0x49, 0x00, 0x00, 0x00, *val, 0xFF],
+            Self::LevelDirect(val) => vec![0x81, 0x01, 0x04, 0x49, 0x00, 0x00, 0x00, val.value(), 0xFF],
 
             Self::PhaseReset => vec![0x81, 0x01, 0x04, 0x0F, 0x00, 0xFF],
             Self::PhaseUp => vec![0x81, 0x01, 0x04, 0x0F, 0x02, 0xFF],
             Self::PhaseDown => vec![0x81, 0x01, 0x04, 0x0F, 0x03, 0xFF],
-            Self::PhaseDirect(val) => vec![0x81, 0x01, 0x04, 0x4F, 0x00, 0x00, 0x00, *val, 0xFF],
-
-            Self::RG(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7A, msg::u8top(*val), msg::u8bot(*val), 0xFF],
-            Self::RB(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7B, msg::u8top(*val), msg::u8bot(*val), 0xFF],
-            Self::GR(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7C, msg::u8top(*val), msg::u8bot(*val), 0xFF],
-            Self::GB(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7D, msg::u8top(*val), msg::u8bot(*val), 0xFF],
-            Self::BR(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7E, msg::u8top(*val), msg::u8bot(*val), 0xFF],
-            Self::BG(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7F, msg::u8top(*val), msg::u8bot(*val), 0xFF],
+            Self::PhaseDirect(val) => vec![0x81, 0x01, 0x04, 0x4F, 0x00, 0x00, 0x00, val.value(), 0xFF],
+
+            Self::RG(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7A, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
+            Self::RB(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7B, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
+            Self::GR(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7C, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
+            Self::GB(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7D, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
+            Self::BR(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7E, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
+            Self::BG(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x7F, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF],
         }
     }
     fn msg_type(&self) -> msg::MessageType {
@@ -291,30 +283,46 @@ impl msg::ViscaMessage for Colour {
 }
 
 impl ViscaCommand for Colour {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Detail {
     LevelReset,
     LevelUp,
     LevelDown,
-    LevelDirect(u8),
-    Mode(u8),
-    Bandwidth(u8),
-    Crispening(u8),
-    HVBalance(u8),
-    BWBalance(u8),
-    Limit(u8),
-    HighlightedTail(u8),
-    Superlow(u8),
+    LevelDirect(DetailLevel),
+    Mode(DetailMode), // the settings below apply in manual mode
+    Bandwidth(DetailBandwidth),
+    Crispening(Crispening),
+    HVBalance(HVBalance),
+    BWBalance(BWBalance),
+    Limit(DetailLimit),
+    HighlightedTail(HighlightDetail),
+    Superlow(SuperLow),
 }
 
 impl msg::ViscaMessage for Detail {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::LevelReset => vec![0x81, 0x01, 0x04, 0x02, 0x00, 0xFF],
+            Self::LevelUp => vec![0x81, 0x01, 0x04, 0x02, 0x02, 0xFF],
+            Self::LevelDown => vec![0x81, 0x01, 0x04, 0x02, 0x03, 0xFF],
+            Self::LevelDirect(val) => {
+                vec![0x81, 0x01, 0x04, 0x42, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+            Self::Mode(val) => vec![0x81, 0x01, 0x05, 0x42, 0x01, val.value(), 0xFF],
+            Self::Bandwidth(val) => vec![0x81, 0x01, 0x05, 0x42, 0x02, val.value(), 0xFF],
+            Self::Crispening(val) => vec![0x81, 0x01, 0x05, 0x42, 0x03, val.value(), 0xFF],
+            Self::HVBalance(val) => vec![0x81, 0x01, 0x05, 0x42, 0x04, val.value(), 0xFF],
+            Self::BWBalance(val) => vec![0x81, 0x01, 0x05, 0x42, 0x05, val.value(), 0xFF],
+            Self::Limit(val) => vec![0x81, 0x01, 0x05, 0x42, 0x06, val.value(), 0xFF],
+            Self::HighlightedTail(val) => vec![0x81, 0x01, 0x05, 0x42, 0x07, val.value(), 0xFF],
+            Self::Superlow(val) => vec![0x81, 0x01, 0x05, 0x42, 0x08, val.value(), 0xFF],
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -322,22 +330,32 @@ impl msg::ViscaMessage for Detail {
 }
 
 impl ViscaCommand for Detail {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Knee {
-    SettingOnOff,
-    Mode(u8),
-    Slope(u8),
-    Point(u8),
+    SettingOnOff(OnOff),
+    Mode(KneeMode),
+    Slope(KneeSlope), // manual mode only
+    Point(KneePoint), // manual mode only
 }
 
 impl msg::ViscaMessage for Knee {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::SettingOnOff(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x6D, val.value(), 0xFF],
+            Self::Mode(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x54, val.value(), 0xFF],
+            Self::Slope(val) => {
+                vec![0x81, 0x01, 0x7E, 0x01, 0x6F, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+            Self::Point(val) => {
+                vec![0x81, 0x01, 0x7E, 0x01, 0x6E, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -345,28 +363,58 @@ impl msg::ViscaMessage for Knee {
 }
 
 impl ViscaCommand for Knee {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Gamma {
-    Mode(u8),
-    Pattern(u8),
-    Offset(u8),
-    Level(u8),
-    BlackGammaLevel(u8),
-    BlackGammaRange(u8),
+    Mode(GammaMode),
+    Pattern(GammaPattern), // used in `GammaMode::Pattern`
+    Offset(GammaOffset),
+    Level(GammaLevel),
+    BlackGammaLevel(BlackGammaLevel),
+    BlackGammaRange(BlackGammaRange),
     BlackLevelReset,
     BlackLevelUp,
     BlackLevelDown,
-    BlackLevelDirect(u8),
+    BlackLevelDirect(BlackLevel),
 }
 
 impl msg::ViscaMessage for Gamma {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::Mode(val) => vec![0x81, 0x01, 0x04, 0x5B, val.value(), 0xFF],
+            Self::Pattern(val) => vec![
+                0x81,
+                0x01,
+                0x05,
+                0x5B,
+                msg::u16midtop(val.value()),
+                msg::u16midbot(val.value()),
+                msg::u16bot(val.value()),
+                0xFF,
+            ],
+            Self::Offset(val) => {
+                let (sign, size) = (u8::from(val.offset() < 0), val.offset().unsigned_abs());
+                vec![0x81, 0x01, 0x04, 0x1E, 0x00, 0x00, 0x00, sign, msg::u8top(size), msg::u8bot(size), 0xFF]
+            }
+            Self::Level(val) => {
+                vec![0x81, 0x01, 0x7E, 0x01, 0x71, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+            Self::BlackGammaLevel(val) => {
+                vec![0x81, 0x01, 0x7E, 0x01, 0x72, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+            Self::BlackGammaRange(val) => vec![0x81, 0x01, 0x7E, 0x01, 0x5A, val.value(), 0xFF],
+            Self::BlackLevelReset => vec![0x81, 0x01, 0x04, 0x15, 0x00, 0xFF],
+            Self::BlackLevelUp => vec![0x81, 0x01, 0x04, 0x15, 0x02, 0xFF],
+            Self::BlackLevelDown => vec![0x81, 0x01, 0x04, 0x15, 0x03, 0xFF],
+            Self::BlackLevelDirect(val) => {
+                vec![0x81, 0x01, 0x04, 0x45, 0x00, 0x00, msg::u8top(val.value()), msg::u8bot(val.value()), 0xFF]
+            }
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -374,19 +422,22 @@ impl msg::ViscaMessage for Gamma {
 }
 
 impl ViscaCommand for Gamma {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PictureProfile {
-    Mode(u8),
+    Mode(PictureProfileMode),
 }
 
 impl msg::ViscaMessage for PictureProfile {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::Mode(val) => vec![0x81, 0x01, 0x7E, 0x04, 0x5F, val.value(), 0xFF],
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -394,19 +445,22 @@ impl msg::ViscaMessage for PictureProfile {
 }
 
 impl ViscaCommand for PictureProfile {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FlickerReduction {
-    Mode(u8),
+    Mode(FlickerReductionMode),
 }
 
 impl msg::ViscaMessage for FlickerReduction {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::Mode(val) => vec![0x81, 0x01, 0x04, 0x32, val.value(), 0xFF],
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -414,20 +468,24 @@ impl msg::ViscaMessage for FlickerReduction {
 }
 
 impl ViscaCommand for FlickerReduction {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NoiseReduction {
-    ModeLevel(u8),
-    NR2d3dSetting(u8),
+    ModeLevel(NRMode),
+    NR2d3dSetting(NRLevel, NRLevel), // 2D, 3D; used in `NRMode::Independent`
 }
 
 impl msg::ViscaMessage for NoiseReduction {
     fn bytes(&self) -> Vec<u8> {
-        todo!()
+        match self {
+            Self::ModeLevel(val) => vec![0x81, 0x01, 0x04, 0x53, val.value(), 0xFF],
+            Self::NR2d3dSetting(p, q) => vec![0x81, 0x01, 0x05, 0x53, p.value(), q.value(), 0xFF],
+        }
     }
     fn msg_type(&self) -> msg::MessageType {
         msg::MessageType::Command
@@ -435,28 +493,29 @@ impl msg::ViscaMessage for NoiseReduction {
 }
 
 impl ViscaCommand for NoiseReduction {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Zoom {
     Stop,
     TeleStd,
     WideStd,
-    TeleVar(u8), // 0 (low) - 7 (high)
-    WideVar(u8), // 0 (low) - 7 (high)
+    TeleVar(VarSpeed),
+    WideVar(VarSpeed),
     Direct(u16), /* 0000 (wide) to 4000 (optical tele)
                   * to 5580 (Clear Image Zoom tele 4K)
                   * 0000 (wide) to 4000 (optical tele)
                   * to 6000 (Clear Image Zoom tele FHD) */
     ClearImageZoomOff,
     ClearImageZoomOn,
-    TeleConvMode(u8), /* 2=double, 3=off
-                       * (Cannot be used when the signal
-                       * format is other than 1080/29.97p,
-                       * 1080/25p, or 1080/23.98p) */
+    TeleConvMode(OnOff), /* On = double
+                          * (Cannot be used when the signal
+                          * format is other than 1080/29.97p,
+                          * 1080/25p, or 1080/23.98p) */
 }
 
 impl msg::ViscaMessage for Zoom {
@@ -465,8 +524,8 @@ impl msg::ViscaMessage for Zoom {
             Self::Stop => vec![0x81, 0x01, 0x04, 0x07, 0x00, 0xFF],
             Self::TeleStd => vec![0x81, 0x01, 0x04, 0x07, 0x02, 0xFF],
             Self::WideStd => vec![0x81, 0x01, 0x04, 0x07, 0x03, 0xFF],
-            Self::TeleVar(val) => vec![0x81, 0x01, 0x04, 0x07, (0x20 | msg::u8bot(*val)), 0xFF],
-            Self::WideVar(val) => vec![0x81, 0x01, 0x04, 0x07, (0x30 | msg::u8bot(*val)), 0xFF],
+            Self::TeleVar(val) => vec![0x81, 0x01, 0x04, 0x07, (0x20 | val.value()), 0xFF],
+            Self::WideVar(val) => vec![0x81, 0x01, 0x04, 0x07, (0x30 | val.value()), 0xFF],
             Self::Direct(val) => vec![
                 0x81,
                 0x01,
@@ -480,7 +539,7 @@ impl msg::ViscaMessage for Zoom {
             ],
             Self::ClearImageZoomOff => vec![0x81, 0x01, 0x04, 0x06, 0x03, 0xFF],
             Self::ClearImageZoomOn => vec![0x81, 0x01, 0x04, 0x06, 0x04, 0xFF],
-            Self::TeleConvMode(val) => vec![0x81, 0x01, 0x7E, 0x04, 0x36, *val, 0xFF],
+            Self::TeleConvMode(val) => vec![0x81, 0x01, 0x7E, 0x04, 0x36, val.value(), 0xFF],
         }
     }
     fn msg_type(&self) -> msg::MessageType {
@@ -489,12 +548,25 @@ impl msg::ViscaMessage for Zoom {
 }
 
 impl ViscaCommand for Zoom {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
+
+    fn bypasses_queue(&self) -> bool {
+        matches!(self, Self::Stop)
+    }
+
+    fn is_motion(&self) -> bool {
+        matches!(self, Self::TeleStd | Self::WideStd | Self::TeleVar(_) | Self::WideVar(_))
+    }
+
+    fn is_ptzf(&self) -> bool {
+        true
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Focus {
     Auto,
     Manual,
@@ -502,14 +574,14 @@ pub enum Focus {
     Stop,
     FarStdSpeed,
     NearStdSpeed,
-    FarVarSpeed(u8),  // 0 (low) - 7 (high)
-    NearVarSpeed(u8), // 0 (low) - 7 (high)
-    Direct(u16),      // F000 (near) - 0000 (far)
+    FarVarSpeed(VarSpeed),
+    NearVarSpeed(VarSpeed),
+    Direct(u16), // F000 (near) - 0000 (far)
     OnePushTrig,
     FocusInf,
-    NearLimit(u16),    // 1000 - F000
-    AFSensitivity(u8), // 2 (normal), 3 (low)
-    IRCorrection(u8),  // 0 (standard), 1 (IR light)
+    NearLimit(u16), // 1000 - F000
+    AFSensitivity(AFSensitivity),
+    IRCorrection(IRCorrection),
 }
 
 impl msg::ViscaMessage for Focus {
@@ -521,8 +593,8 @@ impl msg::ViscaMessage for Focus {
             Focus::Stop => vec![0x81, 0x01, 0x04, 0x08, 0x00, 0xFF],
             Focus::FarStdSpeed => vec![0x81, 0x01, 0x04, 0x08, 0x02, 0xFF],
             Focus::NearStdSpeed => vec![0x81, 0x01, 0x04, 0x08, 0x03, 0xFF],
-            Focus::FarVarSpeed(p) => vec![0x81, 0x01, 0x04, 0x08, (0x20 | msg::u8bot(*p)), 0xFF],
-            Focus::NearVarSpeed(p) => vec![0x81, 0x01, 0x04, 0x08, (0x30 | msg::u8bot(*p)), 0xFF],
+            Focus::FarVarSpeed(p) => vec![0x81, 0x01, 0x04, 0x08, (0x20 | p.value()), 0xFF],
+            Focus::NearVarSpeed(p) => vec![0x81, 0x01, 0x04, 0x08, (0x30 | p.value()), 0xFF],
             Focus::Direct(pppp) => vec![
                 0x81,
                 0x01,
@@ -547,8 +619,8 @@ impl msg::ViscaMessage for Focus {
                 msg::u16bot(*pppp),
                 0xFF,
             ],
-            Focus::AFSensitivity(p) => vec![0x81, 0x01, 0x04, 0x58, *p, 0xFF],
-            Focus::IRCorrection(p) => vec![0x81, 0x01, 0x04, 0x11, *p, 0xFF],
+            Focus::AFSensitivity(p) => vec![0x81, 0x01, 0x04, 0x58, p.value(), 0xFF],
+            Focus::IRCorrection(p) => vec![0x81, 0x01, 0x04, 0x11, p.value(), 0xFF],
         }
     }
     fn msg_type(&self) -> msg::MessageType {
@@ -557,12 +629,28 @@ impl msg::ViscaMessage for Focus {
 }
 
 impl ViscaCommand for Focus {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
+
+    fn bypasses_queue(&self) -> bool {
+        matches!(self, Focus::Stop)
+    }
+
+    fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Focus::FarStdSpeed | Focus::NearStdSpeed | Focus::FarVarSpeed(_) | Focus::NearVarSpeed(_)
+        )
+    }
+
+    fn is_ptzf(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum PanAngle {
     /* TODO THIS NEEDS TO BE FIXED
@@ -631,6 +719,7 @@ impl PanAngle {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum TiltAngle {
     /* TODO THIS NEEDS TO BE FIXED
@@ -670,46 +759,47 @@ impl TiltAngle {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PanTilt {
-    Up(u8, u8),                // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    Down(u8, u8),              // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    Left(u8, u8),              // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    Right(u8, u8),             // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    UpLeft(u8, u8),            // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    UpRight(u8, u8),           // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    DownLeft(u8, u8),          // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    DownRight(u8, u8),         // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    Stop(u8, u8),              // vv: Pan speed ww: Tilt speed 01 (Slow) - 18 (Fast)
-    AbsolutePos(u8, u32, u16), // vv: Speed 01 (Slow) - 18 (Fast) p: Pan angle t: tilt angle
-    RelativePos(u8, u32, u16), // vv: Speed 01 (Slow) - 18 (Fast) p: Pan angle t: tilt angle
+    Up(PanTiltSpeed, PanTiltSpeed),        // vv: Pan speed ww: Tilt speed
+    Down(PanTiltSpeed, PanTiltSpeed),      // vv: Pan speed ww: Tilt speed
+    Left(PanTiltSpeed, PanTiltSpeed),      // vv: Pan speed ww: Tilt speed
+    Right(PanTiltSpeed, PanTiltSpeed),     // vv: Pan speed ww: Tilt speed
+    UpLeft(PanTiltSpeed, PanTiltSpeed),    // vv: Pan speed ww: Tilt speed
+    UpRight(PanTiltSpeed, PanTiltSpeed),   // vv: Pan speed ww: Tilt speed
+    DownLeft(PanTiltSpeed, PanTiltSpeed),  // vv: Pan speed ww: Tilt speed
+    DownRight(PanTiltSpeed, PanTiltSpeed), // vv: Pan speed ww: Tilt speed
+    Stop(PanTiltSpeed, PanTiltSpeed),      // vv: Pan speed ww: Tilt speed
+    AbsolutePos(PanTiltSpeed, u32, u16),   // vv: Speed p: Pan angle t: tilt angle
+    RelativePos(PanTiltSpeed, u32, u16),   // vv: Speed p: Pan angle t: tilt angle
     Home,
     Reset,
-    RampCurve(u8),          // p: 1 (Sharpness), 2 (Standard), 3 (Gentle)
-    SlowMode(u8),           // p: 2=On, 3=Off
-    LimitSet(u8, u32, u16), // w: Position (1=UpRight, 0=DownLeft) p: Pan position t: Tilt position
-    LimitClear(u8),         // w: Position (1=UpRight, 0=DownLeft)
+    RampCurve(RampCurve),
+    SlowMode(OnOff),
+    LimitSet(LimitCorner, u32, u16), // p: Pan position t: Tilt position
+    LimitClear(LimitCorner),
 }
 
 impl msg::ViscaMessage for PanTilt {
     fn bytes(&self) -> Vec<u8> {
         match self {
-            Self::Up(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x03, 0x01, 0xFF],
-            Self::Down(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x03, 0x02, 0xFF],
-            Self::Left(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x01, 0x03, 0xFF],
-            Self::Right(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x02, 0x03, 0xFF],
-            Self::UpLeft(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x01, 0x01, 0xFF],
-            Self::UpRight(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x02, 0x01, 0xFF],
-            Self::DownLeft(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x01, 0x02, 0xFF],
-            Self::DownRight(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x02, 0x02, 0xFF],
-            Self::Stop(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, *vv, *ww, 0x03, 0x03, 0xFF],
+            Self::Up(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x03, 0x01, 0xFF],
+            Self::Down(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x03, 0x02, 0xFF],
+            Self::Left(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x01, 0x03, 0xFF],
+            Self::Right(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x02, 0x03, 0xFF],
+            Self::UpLeft(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x01, 0x01, 0xFF],
+            Self::UpRight(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x02, 0x01, 0xFF],
+            Self::DownLeft(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x01, 0x02, 0xFF],
+            Self::DownRight(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x02, 0x02, 0xFF],
+            Self::Stop(vv, ww) => vec![0x81, 0x01, 0x06, 0x01, vv.value(), ww.value(), 0x03, 0x03, 0xFF],
             Self::AbsolutePos(vv, p, t) => {
                 vec![
                     0x81,
                     0x01,
                     0x06,
                     0x02,
-                    *vv,
+                    vv.value(),
                     0x00,
                     msg::u32byte4(*p),
                     msg::u32byte3(*p),
@@ -729,7 +819,7 @@ impl msg::ViscaMessage for PanTilt {
                     0x01,
                     0x06,
                     0x03,
-                    *vv,
+                    vv.value(),
                     0x00,
                     msg::u32byte4(*p),
                     msg::u32byte3(*p),
@@ -745,8 +835,8 @@ impl msg::ViscaMessage for PanTilt {
             }
             Self::Home => vec![0x81, 0x01, 0x06, 0x04, 0xFF],
             Self::Reset => vec![0x81, 0x01, 0x06, 0x05, 0xFF],
-            Self::RampCurve(p) => vec![0x81, 0x01, 0x06, 0x31, *p, 0xFF],
-            Self::SlowMode(p) => vec![0x81, 0x01, 0x06, 0x44, *p, 0xFF],
+            Self::RampCurve(p) => vec![0x81, 0x01, 0x06, 0x31, p.value(), 0xFF],
+            Self::SlowMode(p) => vec![0x81, 0x01, 0x06, 0x44, p.value(), 0xFF],
             Self::LimitSet(w, p, t) => {
                 vec![
                     0x81,
@@ -754,7 +844,7 @@ impl msg::ViscaMessage for PanTilt {
                     0x06,
                     0x07,
                     0x00,
-                    *w,
+                    w.value(),
                     msg::u32byte4(*p),
                     msg::u32byte3(*p),
                     msg::u32byte2(*p),
@@ -768,7 +858,7 @@ impl msg::ViscaMessage for PanTilt {
                 ]
             }
             Self::LimitClear(w) => vec![
-                0x81, 0x01, 0x06, 0x07, 0x01, *w, 0x07, 0x0F, 0x0F, 0x0F, 0x0F, 0x07, 0x0F, 0x0F,
+                0x81, 0x01, 0x06, 0x07, 0x01, w.value(), 0x07, 0x0F, 0x0F, 0x0F, 0x0F, 0x07, 0x0F, 0x0F,
                 0x0F, 0xFF,
             ],
         }
@@ -779,31 +869,53 @@ impl msg::ViscaMessage for PanTilt {
 }
 
 impl ViscaCommand for PanTilt {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
+
+    fn bypasses_queue(&self) -> bool {
+        matches!(self, Self::Stop(..))
+    }
+
+    fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Self::Up(..)
+                | Self::Down(..)
+                | Self::Left(..)
+                | Self::Right(..)
+                | Self::UpLeft(..)
+                | Self::UpRight(..)
+                | Self::DownLeft(..)
+                | Self::DownRight(..)
+        )
+    }
+
+    fn is_ptzf(&self) -> bool {
+        true
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Preset {
-    Reset(u8),  // memory number
-    Set(u8),    // memory number
-    Recall(u8), // memory number
-    PresetDriveSpeed(u8, u8), /* pp: Preset number of speed setting –1 (00 - 63)
-                 * qq: pp position direction speed 01 - 18 */
-    PresetMode(u8), //00=MODE1, 01=MODE2, 10=TRACE
+    Reset(PresetNumber),
+    Set(PresetNumber),
+    Recall(PresetNumber),
+    PresetDriveSpeed(PresetNumber, PanTiltSpeed), // speed used when recalling the memory
+    PresetMode(PresetMode),
 }
 
 impl msg::ViscaMessage for Preset {
     fn bytes(&self) -> Vec<u8> {
         match self {
-            Preset::Reset(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x00, *p, 0xFF],
-            Preset::Set(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x01, *p, 0xFF],
-            Preset::Recall(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x02, *p, 0xFF],
+            Preset::Reset(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x00, p.value(), 0xFF],
+            Preset::Set(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x01, p.value(), 0xFF],
+            Preset::Recall(p) => vec![0x81, 0x01, 0x04, 0x3F, 0x02, p.value(), 0xFF],
             Preset::PresetDriveSpeed(pp, qq) => {
-                vec![0x81, 0x01, 0x7E, 0x01, 0x0B, *pp, *qq, 0xFF]
+                vec![0x81, 0x01, 0x7E, 0x01, 0x0B, pp.value(), qq.value(), 0xFF]
             }
-            Preset::PresetMode(pp) => vec![0x81, 0x01, 0x7E, 0x04, 0x3D, *pp, 0xFF],
+            Preset::PresetMode(pp) => vec![0x81, 0x01, 0x7E, 0x04, 0x3D, pp.value(), 0xFF],
         }
     }
 
@@ -813,18 +925,19 @@ impl msg::ViscaMessage for Preset {
 }
 
 impl ViscaCommand for Preset {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PTZTrace {
-    RecStart(u8), // 0 - F (PTZ Trace number to record 1 - 16)}
+    RecStart(TraceNumber), // trace to record
     RecStop,
-    PlayPrepare(u8), // 0 - F (PTZ Trace number to reproduce 1 - 16)
+    PlayPrepare(TraceNumber), // trace to reproduce
     PlayStart,
-    Delete(u8), // 0 - F (PTZ Trace number to delete 1 - 16)
+    Delete(TraceNumber),
 }
 
 impl msg::ViscaMessage for PTZTrace {
@@ -838,27 +951,30 @@ impl msg::ViscaMessage for PTZTrace {
 }
 
 impl ViscaCommand for PTZTrace {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum System {
-    IRReceive(u8), // 02=on, 03=off, 10=toggle
+    IRReceive(OnOff),
+    IRReceiveToggle,
     HPhaseUp,
     HPhaseDown,
     HPhaseDirect(u16),    // ppp: 000 - 3BF
-    ImgFlipOnOff(u8),     // p: 2=On, 3=Off
-    PanReverseOnOFf(u8),  // p: 1=ON, 0=OFF
-    TiltReverseOnOff(u8), // p: 1=ON, 0=OFF
+    ImgFlipOnOff(OnOff),
+    PanReverseOnOFf(OnOff),
+    TiltReverseOnOff(OnOff),
     MenuDisplayOff,
 }
 
 impl msg::ViscaMessage for System {
     fn bytes(&self) -> Vec<u8> {
         match self {
-            System::IRReceive(p) => vec![0x81, 0x01, 0x06, 0x08, *p, 0xFF],
+            System::IRReceive(p) => vec![0x81, 0x01, 0x06, 0x08, p.value(), 0xFF],
+            System::IRReceiveToggle => vec![0x81, 0x01, 0x06, 0x08, 0x10, 0xFF],
             System::HPhaseUp => vec![0x81, 0x01, 0x7E, 0x01, 0x3E, 0x00, 0x02, 0xFF],
             System::HPhaseDown => vec![0x81, 0x01, 0x7E, 0x01, 0x3E, 0x00, 0x03, 0xFF],
             System::HPhaseDirect(ppp) => vec![
@@ -874,9 +990,9 @@ impl msg::ViscaMessage for System {
                 msg::u16bot(*ppp),
                 0xFF,
             ],
-            System::ImgFlipOnOff(p) => vec![0x81, 0x01, 0x04, 0x66, *p, 0xFF],
-            System::PanReverseOnOFf(p) => vec![0x81, 0x01, 0x7E, 0x01, 0x06, 0x00, *p, 0xFF],
-            System::TiltReverseOnOff(p) => vec![0x81, 0x01, 0x7E, 0x01, 0x09, 0x00, *p, 0xFF],
+            System::ImgFlipOnOff(p) => vec![0x81, 0x01, 0x04, 0x66, p.value(), 0xFF],
+            System::PanReverseOnOFf(p) => vec![0x81, 0x01, 0x7E, 0x01, 0x06, 0x00, p.bit(), 0xFF],
+            System::TiltReverseOnOff(p) => vec![0x81, 0x01, 0x7E, 0x01, 0x09, 0x00, p.bit(), 0xFF],
             System::MenuDisplayOff => vec![0x81, 0x01, 0x06, 0x06, 0x03, 0xFF],
         }
     }
@@ -887,12 +1003,43 @@ impl msg::ViscaMessage for System {
 }
 
 impl ViscaCommand for System {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cancel(pub Socket); // socket of the command to cancel
+
+impl msg::ViscaMessage for Cancel {
+    fn bytes(&self) -> Vec<u8> {
+        vec![0x81, 0x20 | self.0.value(), 0xFF]
+    }
+
+    fn msg_type(&self) -> msg::MessageType {
+        msg::MessageType::Command
+    }
+}
+
+impl ViscaCommand for Cancel {
+    // The camera answers a cancel with the cancelled command's error reply
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        let socket = self.0.value();
+        match bytes {
+            [0x90, y, 0x04, 0xFF] if *y == 0x60 | socket => format!("Command on socket {} cancelled", socket),
+            [0x90, y, 0x05, 0xFF] if *y == 0x60 | socket => format!("No command on socket {} to cancel", socket),
+            x => format!("Unknown reply: {:?}", x),
+        }
+    }
+
+    fn bypasses_queue(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HDMI {}
 
 impl msg::ViscaMessage for HDMI {
@@ -906,7 +1053,74 @@ impl msg::ViscaMessage for HDMI {
 }
 
 impl ViscaCommand for HDMI {
-    fn parse_reply(&self, bytes: &[u8]) -> String {
+    fn parse_reply(&self, _bytes: &[u8]) -> String {
         todo!()
     }
-}
\ No newline at end of file
+}
+
+// Any command, for code that stores or forwards commands of mixed kinds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command {
+    Exposure(Exposure),
+    Colour(Colour),
+    Zoom(Zoom),
+    Focus(Focus),
+    PanTilt(PanTilt),
+    Preset(Preset),
+    System(System),
+    Cancel(Cancel),
+    Detail(Detail),
+    Knee(Knee),
+    Gamma(Gamma),
+}
+
+macro_rules! command_from {
+    ($($kind:ident),+) => {
+        $(impl From<$kind> for Command {
+            fn from(command: $kind) -> Self {
+                Command::$kind(command)
+            }
+        })+
+
+        impl msg::ViscaMessage for Command {
+            fn bytes(&self) -> Vec<u8> {
+                match self {
+                    $(Command::$kind(c) => c.bytes()),+
+                }
+            }
+
+            fn msg_type(&self) -> msg::MessageType {
+                msg::MessageType::Command
+            }
+        }
+
+        impl ViscaCommand for Command {
+            fn parse_reply(&self, bytes: &[u8]) -> String {
+                match self {
+                    $(Command::$kind(c) => c.parse_reply(bytes)),+
+                }
+            }
+
+            fn bypasses_queue(&self) -> bool {
+                match self {
+                    $(Command::$kind(c) => c.bypasses_queue()),+
+                }
+            }
+
+            fn is_motion(&self) -> bool {
+                match self {
+                    $(Command::$kind(c) => c.is_motion()),+
+                }
+            }
+
+            fn is_ptzf(&self) -> bool {
+                match self {
+                    $(Command::$kind(c) => c.is_ptzf()),+
+                }
+            }
+        }
+    };
+}
+
+command_from!(Exposure, Colour, Zoom, Focus, PanTilt, Preset, System, Cancel, Detail, Knee, Gamma);
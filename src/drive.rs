@@ -0,0 +1,245 @@
+// Continuous pan/tilt/zoom/focus driving from analogue inputs such as a joystick.
+//
+// Inputs are normalised axes (-1.0 to 1.0). Each axis is mapped onto the
+// camera's speed range, and a command is only sent when the resulting speed
+// changes, at most once per `DriveConfig::min_interval`. With a measured
+// `SpeedTable` in `DriveConfig::speeds` the response is linear in actual
+// degrees per second rather than in speed settings. Returning an axis to
+// centre always stops it immediately, and dropping the `Drive` sends pan/tilt,
+// zoom and focus stops even if the position can't be read. Pair with
+// `Camera::enable_watchdog` to also stop the camera if updates stop arriving.
+// A `Camera::set_geofence` is enforced as the camera moves, stopping it short
+// of any no-go zone.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::commands::{Focus, PanTilt, Zoom};
+use crate::motion::SpeedTable;
+use crate::params::{PanTiltSpeed, VarSpeed};
+use crate::{limits, Camera};
+
+#[derive(Debug, Clone)]
+pub struct DriveConfig {
+    pub deadzone: f32,          // fraction of travel around centre treated as 0
+    pub curve: f32,             // response exponent, 1.0 = linear, >1.0 = finer control near centre
+    pub min_interval: Duration, // minimum time between commands for a moving axis
+    pub max_pan_speed: PanTiltSpeed,
+    pub max_tilt_speed: PanTiltSpeed,
+    pub max_zoom_speed: VarSpeed,
+    pub max_focus_speed: VarSpeed,
+    pub speeds: Option<SpeedTable>, // map axes by measured speed rather than by setting
+}
+
+impl Default for DriveConfig {
+    fn default() -> Self {
+        DriveConfig {
+            deadzone: 0.1,
+            curve: 2.0,
+            min_interval: Duration::from_millis(100),
+            max_pan_speed: PanTiltSpeed::MAX,
+            max_tilt_speed: PanTiltSpeed::MAX,
+            max_zoom_speed: VarSpeed::MAX,
+            max_focus_speed: VarSpeed::MAX,
+            speeds: None,
+        }
+    }
+}
+
+// Signed speed per axis as it will be sent to the camera, 0 = stopped.
+// Zoom and focus hold the VISCA speed + 1 so that speed 0 (slowest) still moves.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Speeds {
+    pan: i8,
+    tilt: i8,
+    zoom: i8,
+    focus: i8,
+}
+
+pub struct Drive<'a> {
+    camera: &'a mut Camera,
+    config: DriveConfig,
+    target: Speeds,
+    sent: Speeds,
+    last_pan_tilt: Option<Instant>,
+    last_zoom: Option<Instant>,
+    last_focus: Option<Instant>,
+}
+
+impl<'a> Drive<'a> {
+    pub fn new(camera: &'a mut Camera, config: DriveConfig) -> Self {
+        Drive {
+            camera,
+            config,
+            target: Speeds::default(),
+            sent: Speeds::default(),
+            last_pan_tilt: None,
+            last_zoom: None,
+            last_focus: None,
+        }
+    }
+
+    /// Sets the axes, each from -1.0 to 1.0. Positive pan is right, tilt up,
+    /// zoom tele and focus far.
+    ///
+    /// Call this every time the input is read; changes that arrive faster than
+    /// `min_interval` are coalesced and sent by a later `update` or `tick`.
+    pub fn update(&mut self, pan: f32, tilt: f32, zoom: f32, focus: f32) -> io::Result<()> {
+        // Holding a position sends nothing new, but still counts as the operator
+        // being present if the camera's watchdog is enabled
+        self.camera.feed_watchdog();
+        let c = &self.config;
+        self.target = match &c.speeds {
+            Some(table) => {
+                let shaped = |value| shape(value, c.deadzone, c.curve);
+                Speeds {
+                    pan: by_rate(shaped(pan), table.pan_rate(c.max_pan_speed), |r| table.pan_speed(r).value()),
+                    tilt: by_rate(shaped(tilt), table.tilt_rate(c.max_tilt_speed), |r| table.tilt_speed(r).value()),
+                    zoom: by_rate(shaped(zoom), table.zoom_rate(c.max_zoom_speed), |r| table.zoom_speed(r).value() + 1),
+                    focus: map_axis(focus, c.deadzone, c.curve, 1, c.max_focus_speed.value() + 1),
+                }
+            }
+            None => Speeds {
+                pan: map_axis(pan, c.deadzone, c.curve, 1, c.max_pan_speed.value()),
+                tilt: map_axis(tilt, c.deadzone, c.curve, 1, c.max_tilt_speed.value()),
+                zoom: map_axis(zoom, c.deadzone, c.curve, 1, c.max_zoom_speed.value() + 1),
+                focus: map_axis(focus, c.deadzone, c.curve, 1, c.max_focus_speed.value() + 1),
+            },
+        };
+        self.tick()
+    }
+
+    /// Sends any change held back by the rate limit once it is due
+    pub fn tick(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        let interval = self.config.min_interval;
+        let due = |last: Option<Instant>| last.is_none_or(|t| now.duration_since(t) >= interval);
+
+        // A geofence stop leaves the camera still, and the held input is sent
+        // again to be checked from where it stopped
+        if self.camera.check_geofence().map_err(limits::into_io)? {
+            self.sent = Speeds { focus: self.sent.focus, ..Speeds::default() };
+        }
+
+        // An axis returning to centre is never held back by the rate limit
+        let (pan, tilt) = (self.target.pan, self.target.tilt);
+        let centred = (pan == 0 && self.sent.pan != 0) || (tilt == 0 && self.sent.tilt != 0);
+        if (pan, tilt) != (self.sent.pan, self.sent.tilt) && (centred || due(self.last_pan_tilt)) {
+            self.camera.set(pan_tilt_command(pan, tilt))?;
+            self.sent.pan = pan;
+            self.sent.tilt = tilt;
+            self.last_pan_tilt = Some(now);
+        }
+
+        let zoom = self.target.zoom;
+        if zoom != self.sent.zoom && (zoom == 0 || due(self.last_zoom)) {
+            self.camera.set(zoom_command(zoom))?;
+            self.sent.zoom = zoom;
+            self.last_zoom = Some(now);
+        }
+
+        let focus = self.target.focus;
+        if focus != self.sent.focus && (focus == 0 || due(self.last_focus)) {
+            self.camera.set(focus_command(focus))?;
+            self.sent.focus = focus;
+            self.last_focus = Some(now);
+        }
+        Ok(())
+    }
+
+    /// Stops every axis. All three stops are sent straight away, without the
+    /// geofence check or the rate limit, and a failed stop doesn't keep the
+    /// others from being sent; the first failure is returned.
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.target = Speeds::default();
+        self.sent = Speeds::default();
+        let speed = PanTiltSpeed::MIN;
+        let results = [
+            self.camera.set(PanTilt::Stop(speed, speed)),
+            self.camera.set(Zoom::Stop),
+            self.camera.set(Focus::Stop),
+        ];
+        results.into_iter().collect()
+    }
+
+    pub fn config(&self) -> &DriveConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: DriveConfig) {
+        self.config = config;
+    }
+}
+
+impl Drop for Drive<'_> {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// Applies deadzone and response curve to an axis, giving -1.0 to 1.0 with 0.0
+// inside the deadzone
+fn shape(value: f32, deadzone: f32, curve: f32) -> f32 {
+    let value = if value.is_nan() { 0.0 } else { value.clamp(-1.0, 1.0) };
+    let deadzone = deadzone.clamp(0.0, 0.99);
+    let magnitude = value.abs();
+    if magnitude <= deadzone {
+        return 0.0;
+    }
+    let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).powf(curve.max(0.1));
+    if value < 0.0 { -scaled } else { scaled }
+}
+
+// Maps an axis onto min..=max with deadzone and response curve, keeping the sign
+fn map_axis(value: f32, deadzone: f32, curve: f32, min: u8, max: u8) -> i8 {
+    let scaled = shape(value, deadzone, curve);
+    if scaled == 0.0 || max < min {
+        return 0;
+    }
+    let speed = min as f32 + scaled.abs() * (max - min) as f32;
+    let speed = speed.round() as i8;
+    if scaled < 0.0 { -speed } else { speed }
+}
+
+// Maps a shaped axis onto a fraction of `max_rate`, then onto the speed
+// setting `speed` picks for that rate
+fn by_rate(scaled: f32, max_rate: f32, speed: impl Fn(f32) -> u8) -> i8 {
+    if scaled == 0.0 {
+        return 0;
+    }
+    let speed = speed(scaled.abs() * max_rate) as i8;
+    if scaled < 0.0 { -speed } else { speed }
+}
+
+pub(crate) fn pan_tilt_command(pan: i8, tilt: i8) -> PanTilt {
+    // the speed of an axis that isn't moving is ignored but must be valid
+    let vv = PanTiltSpeed::clamped(pan.unsigned_abs());
+    let ww = PanTiltSpeed::clamped(tilt.unsigned_abs());
+    match (pan.signum(), tilt.signum()) {
+        (0, 0) => PanTilt::Stop(vv, ww),
+        (0, 1) => PanTilt::Up(vv, ww),
+        (0, _) => PanTilt::Down(vv, ww),
+        (1, 0) => PanTilt::Right(vv, ww),
+        (1, 1) => PanTilt::UpRight(vv, ww),
+        (1, _) => PanTilt::DownRight(vv, ww),
+        (_, 0) => PanTilt::Left(vv, ww),
+        (_, 1) => PanTilt::UpLeft(vv, ww),
+        (_, _) => PanTilt::DownLeft(vv, ww),
+    }
+}
+
+pub(crate) fn zoom_command(zoom: i8) -> Zoom {
+    match zoom.signum() {
+        0 => Zoom::Stop,
+        1 => Zoom::TeleVar(VarSpeed::clamped(zoom.unsigned_abs() - 1)),
+        _ => Zoom::WideVar(VarSpeed::clamped(zoom.unsigned_abs() - 1)),
+    }
+}
+
+fn focus_command(focus: i8) -> Focus {
+    match focus.signum() {
+        0 => Focus::Stop,
+        1 => Focus::FarVarSpeed(VarSpeed::clamped(focus.unsigned_abs() - 1)),
+        _ => Focus::NearVarSpeed(VarSpeed::clamped(focus.unsigned_abs() - 1)),
+    }
+}
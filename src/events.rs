@@ -0,0 +1,222 @@
+// Change notifications for camera state.
+//
+// `Camera::subscribe` starts a background thread that polls position, zoom,
+// exposure mode and power, each at its own interval from a socket of its own,
+// and reports values that differ from the last ones read, starting with the
+// current values. Changes made from the IR remote or another controller show
+// up the same way as our own. Commands submitted with `Camera::submit` or
+// `execute` are reported as they complete or fail; those replies are read by
+// the `Camera`, so they arrive while it is being polled or waited on.
+
+use std::net::UdpSocket;
+use std::sync::atomic::AtomicU32;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::inquiry;
+use crate::messages::{Packet, Reply, ViscaInquiry, ViscaMessage};
+use crate::params::{ExposureMode, OnOff};
+use crate::queue::{Done, Ticket};
+use crate::{send_packet, MessageType, ViscaError, DEFAULT_TIMEOUT};
+
+#[derive(Debug, Clone)]
+pub enum CameraEvent {
+    PositionChanged(u32, u16), // pan, tilt
+    ZoomChanged(u16),
+    ExposureModeChanged(ExposureMode),
+    PowerChanged(OnOff),
+    CommandCompleted(Ticket),
+    CommandFailed(Ticket, Arc<ViscaError>),
+}
+
+// How often each value is read, None to not watch it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollIntervals {
+    pub position: Option<Duration>,
+    pub zoom: Option<Duration>,
+    pub exposure_mode: Option<Duration>,
+    pub power: Option<Duration>,
+}
+
+impl Default for PollIntervals {
+    fn default() -> Self {
+        PollIntervals {
+            position: Some(Duration::from_millis(250)),
+            zoom: Some(Duration::from_millis(250)),
+            exposure_mode: Some(Duration::from_secs(1)),
+            power: Some(Duration::from_secs(2)),
+        }
+    }
+}
+
+type Subscribers = Arc<Mutex<Vec<Sender<CameraEvent>>>>;
+
+#[derive(Default)]
+pub(crate) struct Events {
+    subscribers: Subscribers,
+    intervals: PollIntervals,
+    poller: Option<Poller>,
+}
+
+struct Poller {
+    shutdown: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Events {
+    pub fn is_polling(&self) -> bool {
+        self.poller.is_some()
+    }
+
+    pub fn intervals(&self) -> PollIntervals {
+        self.intervals
+    }
+
+    pub fn set_intervals(&mut self, intervals: PollIntervals) {
+        self.intervals = intervals;
+    }
+
+    // Starts polling on `socket`, replacing any poller already running
+    pub fn start(&mut self, socket: UdpSocket, seqnum: Arc<AtomicU32>) {
+        self.poller = None;
+        self.poller = Some(Poller::spawn(socket, seqnum, self.intervals, Arc::clone(&self.subscribers)));
+    }
+
+    pub fn subscribe(&self) -> Receiver<CameraEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn command_done(&self, done: &Done) {
+        let event = match done {
+            Done::Completed(ticket, _) => CameraEvent::CommandCompleted(*ticket),
+            Done::Failed(ticket, code) => CameraEvent::CommandFailed(*ticket, Arc::new(ViscaError::from_code(*code))),
+        };
+        emit(&self.subscribers, event);
+    }
+}
+
+// Sends `event` to every subscriber, forgetting those that have gone
+fn emit(subscribers: &Subscribers, event: CameraEvent) {
+    subscribers.lock().unwrap().retain(|s| s.send(event.clone()).is_ok());
+}
+
+impl Poller {
+    fn spawn(socket: UdpSocket, seqnum: Arc<AtomicU32>, intervals: PollIntervals, subscribers: Subscribers) -> Self {
+        let shutdown = Arc::new((Mutex::new(false), Condvar::new()));
+        let stop = Arc::clone(&shutdown);
+
+        let thread = thread::spawn(move || {
+            let watched = [
+                (Watch::Position, intervals.position),
+                (Watch::Zoom, intervals.zoom),
+                (Watch::ExposureMode, intervals.exposure_mode),
+                (Watch::Power, intervals.power),
+            ];
+            let mut due = [Instant::now(); 4];
+            let mut last: [Option<Vec<u8>>; 4] = Default::default(); // reply payloads
+            loop {
+                let next = (0..4).filter(|&i| watched[i].1.is_some()).map(|i| due[i]).min();
+                let wait = next.map_or(Duration::MAX, |t| t.saturating_duration_since(Instant::now()));
+                let (lock, condvar) = &*stop;
+                if *condvar.wait_timeout_while(lock.lock().unwrap(), wait, |s| !*s).unwrap().0 {
+                    return;
+                }
+
+                let now = Instant::now();
+                for (i, (watch, interval)) in watched.iter().enumerate() {
+                    let Some(interval) = interval else { continue };
+                    if due[i] > now {
+                        continue;
+                    }
+                    due[i] = now + *interval;
+                    if subscribers.lock().unwrap().is_empty() {
+                        continue;
+                    }
+                    let Some(reply) = query(&socket, &seqnum, &watch.inquiry()) else { continue };
+                    if last[i].as_ref() != Some(&reply) {
+                        if let Some(event) = watch.event(&reply) {
+                            emit(&subscribers, event);
+                        }
+                        last[i] = Some(reply);
+                    }
+                }
+            }
+        });
+
+        Poller { shutdown, thread: Some(thread) }
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shutdown;
+        *lock.lock().unwrap() = true;
+        condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Watch {
+    Position,
+    Zoom,
+    ExposureMode,
+    Power,
+}
+
+impl Watch {
+    fn inquiry(self) -> Vec<u8> {
+        match self {
+            Watch::Position => inquiry::PanTilt::Position.bytes(),
+            Watch::Zoom => inquiry::Zoom::Position.bytes(),
+            Watch::ExposureMode => inquiry::Exposure::Mode.bytes(),
+            Watch::Power => inquiry::System::Power.bytes(),
+        }
+    }
+
+    fn event(self, reply: &[u8]) -> Option<CameraEvent> {
+        match self {
+            Watch::Position => {
+                inquiry::PanTilt::parse_position(reply).map(|(pan, tilt)| CameraEvent::PositionChanged(pan, tilt))
+            }
+            Watch::Zoom => inquiry::Zoom::Position.parse_value(reply).map(|z| CameraEvent::ZoomChanged(z as u16)),
+            Watch::ExposureMode => {
+                let value = inquiry::Exposure::Mode.parse_value(reply)?;
+                ExposureMode::from_value(value as u8).ok().map(CameraEvent::ExposureModeChanged)
+            }
+            Watch::Power => {
+                let value = inquiry::System::Power.parse_value(reply)?;
+                OnOff::from_value(value as u8).ok().map(CameraEvent::PowerChanged)
+            }
+        }
+    }
+}
+
+// Sends `inquiry` and waits for its answer, None if there is none in time.
+// Also used by the recording sampler.
+pub(crate) fn query(socket: &UdpSocket, seqnum: &AtomicU32, inquiry: &[u8]) -> Option<Vec<u8>> {
+    let start = Instant::now();
+    let sent = send_packet(socket, seqnum, MessageType::Inquiry, inquiry).ok()?;
+    let mut buf = [0u8; 1500];
+    loop {
+        let remaining = DEFAULT_TIMEOUT.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            return None;
+        }
+        socket.set_read_timeout(Some(remaining)).ok()?;
+        let received = socket.recv(&mut buf).ok()?;
+        let Some(packet) = Packet::parse(&buf[..received]) else { continue };
+        if packet.msg_type == MessageType::Reply && packet.seqnum == sent {
+            return match Reply::parse(packet.payload)? {
+                Reply::Completion(..) => Some(packet.payload.to_vec()),
+                _ => None,
+            };
+        }
+    }
+}
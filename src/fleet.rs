@@ -0,0 +1,390 @@
+// A set of named cameras that are operated together.
+//
+// An `Inventory` lists the cameras with their address, model, the groups
+// they belong to and the speeds programs should default to. With the `toml`
+// feature it is read from a file of `[[camera]]` tables:
+//
+//     [[camera]]
+//     name = "stage-left"
+//     address = "192.168.0.10:52381"
+//     model = "BRC-X400"
+//     framing = "lectern close up"
+//     groups = ["stage"]
+//     speeds = { pan_tilt = 12, zoom = 4, focus = 4 }
+//
+// A camera's mount pose, as solved by `geometry::calibrate`, can be kept with
+// it in an optional `pose` table and saved back with `Inventory::save`.
+//
+// `Fleet::connect` opens every camera in it, after which cameras are reached
+// by name, `fleet["stage-left"]`, or a group at a time. `Fleet::broadcast`
+// runs a command on a whole group at once, one thread per camera, and
+// reports how each camera got on.
+//
+// `Fleet::check_consistency` snapshots every camera and compares its picture
+// settings (exposure, colour, detail and gamma, see `CameraState::picture`)
+// against a reference camera. A camera that can't be read doesn't stop the
+// check; its `Drift` carries the error instead.
+
+use std::fmt;
+use std::io;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::Command;
+use crate::geometry::Pose;
+use crate::inquiry;
+use crate::messages::{ViscaCommand, ViscaInquiry};
+use crate::params::{PanTiltSpeed, VarSpeed};
+use crate::state::{self, Difference};
+use crate::{Camera, HealthState, Result, ViscaError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Transport {
+    #[default]
+    Udp, // VISCA over IP
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DefaultSpeeds {
+    pub pan_tilt: PanTiltSpeed,
+    pub zoom: VarSpeed,
+    pub focus: VarSpeed,
+}
+
+impl Default for DefaultSpeeds {
+    fn default() -> Self {
+        DefaultSpeeds {
+            pan_tilt: PanTiltSpeed::clamped(0x0C),
+            zoom: VarSpeed::clamped(0x04),
+            focus: VarSpeed::clamped(0x04),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraConfig {
+    pub name: String,
+    pub address: String, // host:port
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub model: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub framing: String, // what the camera is there to shoot, for operators
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub transport: Transport,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub speeds: DefaultSpeeds,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub pose: Option<Pose>, // where the camera is mounted, e.g. from `geometry::calibrate`
+}
+
+impl CameraConfig {
+    pub fn new(name: &str, address: &str) -> Self {
+        CameraConfig {
+            name: name.to_string(),
+            address: address.to_string(),
+            model: String::new(),
+            framing: String::new(),
+            transport: Transport::default(),
+            groups: Vec::new(),
+            speeds: DefaultSpeeds::default(),
+            pose: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    #[cfg_attr(feature = "serde", serde(rename = "camera", default))]
+    pub cameras: Vec<CameraConfig>,
+}
+
+#[cfg(feature = "toml")]
+impl Inventory {
+    pub fn load(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let inventory: Inventory = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        inventory.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(inventory)
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        let text = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+}
+
+impl Inventory {
+    // Names must be present and unique to be used as keys
+    fn validate(&self) -> Result<()> {
+        for (i, camera) in self.cameras.iter().enumerate() {
+            if camera.name.is_empty() || self.cameras[..i].iter().any(|c| c.name == camera.name) {
+                return Err(ViscaError::ParameterOutOfRange("camera name"));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Answer of one camera to `Fleet::health`
+#[derive(Debug)]
+pub struct Health {
+    pub name: String,
+    pub round_trip: Result<Duration>, // of a power inquiry
+    pub standby: bool,
+    pub state: Option<HealthState>, // from the camera's heartbeat, if enabled
+}
+
+// How far one camera has drifted from the reference
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub name: String,
+    pub differences: Vec<Difference>,
+    pub commands: Vec<Command>, // bring the camera in line with the reference
+    pub error: Option<Arc<ViscaError>>, // the camera couldn't be read, nothing else is set
+}
+
+// How one camera got on with a `Fleet::broadcast`
+#[derive(Debug)]
+pub struct Outcome {
+    pub name: String,
+    pub result: Result<()>,
+    pub elapsed: Duration, // until completion or failure
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub outcomes: Vec<Outcome>, // in fleet order
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.iter().all(|o| o.result.is_ok())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Outcome> {
+        self.outcomes.iter().filter(|o| o.result.is_err())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.outcomes.iter().map(|o| o.name.len()).max().unwrap_or(0);
+        for o in &self.outcomes {
+            let ms = o.elapsed.as_millis();
+            match &o.result {
+                Ok(()) => writeln!(f, "{:width$}  ok      {:>6} ms", o.name, ms)?,
+                Err(e) => writeln!(f, "{:width$}  failed  {:>6} ms  {}", o.name, ms, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Member {
+    config: CameraConfig,
+    camera: Camera,
+}
+
+#[derive(Default)]
+pub struct Fleet {
+    cameras: Vec<Member>,
+}
+
+impl Fleet {
+    pub fn new() -> Self {
+        Fleet::default()
+    }
+
+    /// Connects to every camera in `inventory`
+    pub fn connect(inventory: &Inventory) -> Result<Self> {
+        inventory.validate()?;
+        let mut fleet = Fleet::new();
+        for config in &inventory.cameras {
+            let camera = Camera::new(&config.address)?;
+            fleet.cameras.push(Member { config: config.clone(), camera });
+        }
+        Ok(fleet)
+    }
+
+    /// Adds a camera under `name`, replacing any camera already using it
+    pub fn add(&mut self, name: &str, camera: Camera) {
+        let address = camera.socket.peer_addr().map_or(String::new(), |a| a.to_string());
+        self.add_configured(CameraConfig::new(name, &address), camera);
+    }
+
+    /// Adds a camera with its inventory entry, replacing any camera already
+    /// using its name
+    pub fn add_configured(&mut self, config: CameraConfig, camera: Camera) {
+        match self.cameras.iter_mut().find(|m| m.config.name == config.name) {
+            Some(member) => *member = Member { config, camera },
+            None => self.cameras.push(Member { config, camera }),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Camera> {
+        let index = self.cameras.iter().position(|m| m.config.name == name)?;
+        Some(self.cameras.remove(index).camera)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Camera> {
+        self.cameras.iter().find(|m| m.config.name == name).map(|m| &m.camera)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Camera> {
+        self.cameras.iter_mut().find(|m| m.config.name == name).map(|m| &mut m.camera)
+    }
+
+    pub fn config(&self, name: &str) -> Option<&CameraConfig> {
+        self.cameras.iter().find(|m| m.config.name == name).map(|m| &m.config)
+    }
+
+    /// The fleet as an inventory, for saving
+    pub fn inventory(&self) -> Inventory {
+        Inventory { cameras: self.cameras.iter().map(|m| m.config.clone()).collect() }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.cameras.iter().map(|m| m.config.name.as_str())
+    }
+
+    /// Names of the cameras in `group`
+    pub fn group<'a>(&'a self, group: &'a str) -> impl Iterator<Item = &'a str> {
+        self.cameras
+            .iter()
+            .filter(move |m| m.config.groups.iter().any(|g| g == group))
+            .map(|m| m.config.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cameras.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty()
+    }
+
+    /// Compares the picture settings of every camera with those of
+    /// `reference`, returning one `Drift` for each camera that differs or
+    /// couldn't be read. Fails only if the reference can't be read.
+    pub fn check_consistency(&mut self, reference: &str) -> Result<Vec<Drift>> {
+        let camera = self.get_mut(reference).ok_or(ViscaError::ParameterOutOfRange("camera name"))?;
+        let target = camera.snapshot()?.picture();
+
+        let mut drift = Vec::new();
+        for Member { config, camera } in self.cameras.iter_mut().filter(|m| m.config.name != reference) {
+            let name = config.name.clone();
+            let current = match camera.snapshot() {
+                Ok(state) => state.picture(),
+                Err(e) => {
+                    drift.push(Drift { name, differences: Vec::new(), commands: Vec::new(), error: Some(Arc::new(e)) });
+                    continue;
+                }
+            };
+            let differences = state::diff(&target, &current);
+            if !differences.is_empty() {
+                drift.push(Drift { name, differences, commands: target.commands_from(&current), error: None });
+            }
+        }
+        Ok(drift)
+    }
+
+    /// Sends each camera the commands from its `Drift`
+    pub fn align(&mut self, drift: &[Drift]) -> Result<()> {
+        for d in drift {
+            let camera = self.get_mut(&d.name).ok_or(ViscaError::ParameterOutOfRange("camera name"))?;
+            for command in &d.commands {
+                let timeout = camera.timeout.unwrap_or(crate::DEFAULT_TIMEOUT);
+                camera.execute(command.clone(), timeout)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends `command` with `Camera::set` to every camera in `group`, one
+    /// after the other, without waiting for completions
+    pub fn set_group(&mut self, group: &str, command: impl ViscaCommand + Clone) -> Vec<(String, io::Result<()>)> {
+        self.cameras
+            .iter_mut()
+            .filter(|m| m.config.groups.iter().any(|g| g == group))
+            .map(|m| (m.config.name.clone(), m.camera.set(command.clone())))
+            .collect()
+    }
+
+    /// Executes `command` on every camera in `group`, or the whole fleet if
+    /// None, all at the same time, waiting up to `timeout` for each to
+    /// complete
+    pub fn broadcast(
+        &mut self,
+        group: Option<&str>,
+        command: impl ViscaCommand + Clone + Send,
+        timeout: Duration,
+    ) -> Report {
+        let members = self
+            .cameras
+            .iter_mut()
+            .filter(|m| group.is_none_or(|group| m.config.groups.iter().any(|g| g == group)));
+        let outcomes = thread::scope(|scope| {
+            let running: Vec<_> = members
+                .map(|Member { config, camera }| {
+                    let command = command.clone();
+                    let thread = scope.spawn(move || {
+                        let start = Instant::now();
+                        let result = camera.execute(command, timeout);
+                        (result, start.elapsed())
+                    });
+                    (config.name.clone(), thread)
+                })
+                .collect();
+            running
+                .into_iter()
+                .map(|(name, thread)| {
+                    let (result, elapsed) = thread.join().expect("broadcast thread panicked");
+                    Outcome { name, result, elapsed }
+                })
+                .collect()
+        });
+        Report { outcomes }
+    }
+
+    /// Asks every camera for its power state, as a check that it answers
+    pub fn health(&mut self) -> Vec<Health> {
+        self.cameras
+            .iter_mut()
+            .map(|m| {
+                let start = Instant::now();
+                let reply = m.camera.inquire(&inquiry::System::Power);
+                Health {
+                    name: m.config.name.clone(),
+                    standby: reply.as_ref().is_ok_and(|r| inquiry::System::Power.parse_value(r) == Some(0x03)),
+                    round_trip: reply.map(|_| start.elapsed()),
+                    state: m.camera.health(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Index<&str> for Fleet {
+    type Output = Camera;
+
+    // Panics if there is no camera called `name`, use `get` to check first
+    fn index(&self, name: &str) -> &Camera {
+        self.get(name).unwrap_or_else(|| panic!("no camera named {:?} in the fleet", name))
+    }
+}
+
+impl IndexMut<&str> for Fleet {
+    fn index_mut(&mut self, name: &str) -> &mut Camera {
+        self.get_mut(name).unwrap_or_else(|| panic!("no camera named {:?} in the fleet", name))
+    }
+}
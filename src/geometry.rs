@@ -0,0 +1,442 @@
+// Pointing a camera at a point in the room.
+//
+// World coordinates are in metres with z up. A `Pose` places a camera's base
+// in the world: `yaw` is the direction pan 0 faces, in degrees anticlockwise
+// from the x axis seen from above, `pitch` raises the front of the base and
+// `roll` tips it to the right. From a pose, `Pose::aim` gives the pan/tilt
+// angles that centre a target, and `Camera::look_at` sends them, with a zoom
+// chosen to frame a given width at the target if a `Lens` is supplied.
+//
+// A camera hung from the ceiling is upside down, so its pan and tilt run the
+// opposite way unless image flip is on, which the camera compensates for.
+// Pan and tilt reverse flip each axis again. The pose must match how the camera
+// is configured; `Pose::setup_commands` configures a camera to match the pose.
+//
+// A pose can be measured by centring the camera on three or more points whose
+// positions are known, recording each with `Camera::sight`, and passing the
+// sightings to `calibrate`. It finds the position and orientation that best
+// explain them by least squares, started from a spread of guesses around the
+// points so it doesn't settle on a poor local fit. A pose is saved as
+// `key = value` lines with `Pose::save`, or kept with the camera in a fleet
+// inventory as `CameraConfig::pose`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::commands::{Command, PanTilt, System, Zoom};
+use crate::motion;
+use crate::optics::Lens;
+use crate::params::{OnOff, PanTiltSpeed};
+use crate::state::MOVE_TIMEOUT;
+use crate::{Camera, Result, ViscaError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Point { x, y, z }
+    }
+
+    pub fn distance(self, other: Point) -> f32 {
+        let (x, y, z) = (other.x - self.x, other.y - self.y, other.z - self.z);
+        (x * x + y * y + z * z).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Pose {
+    pub position: Point,
+    pub yaw: f32, // degrees
+    pub pitch: f32,
+    pub roll: f32,
+    pub ceiling: bool, // hung upside down
+    pub image_flip: bool,
+    pub pan_reverse: bool,
+    pub tilt_reverse: bool,
+}
+
+impl Pose {
+    /// Pan and tilt in degrees, as sent to the camera, that centre `target`
+    pub fn aim(&self, target: Point) -> (f32, f32) {
+        let d = [target.x - self.position.x, target.y - self.position.y, target.z - self.position.z];
+        // Into the base's frame: x forward, y left, z up. The base is rotated
+        // by roll, then pitch, then yaw, so undo them in the reverse order.
+        let [x, y, z] = rotate_z(d, -self.yaw);
+        let [x, y, z] = rotate_y([x, y, z], -self.pitch);
+        let [x, y, z] = rotate_x([x, y, z], -self.roll);
+
+        let mut pan = y.atan2(x).to_degrees();
+        let mut tilt = z.atan2((x * x + y * y).sqrt()).to_degrees();
+        // Upside down both axes turn the other way
+        if self.ceiling != self.image_flip {
+            pan = -pan;
+            tilt = -tilt;
+        }
+        if self.pan_reverse {
+            pan = -pan;
+        }
+        if self.tilt_reverse {
+            tilt = -tilt;
+        }
+        (pan, tilt)
+    }
+
+    /// Zoom position whose field of view is `width` metres across at `target`,
+    /// limited to the zoom range of `lens`
+    pub fn framing(&self, lens: &Lens, target: Point, width: f32) -> u16 {
+        let distance = self.position.distance(target).max(f32::EPSILON);
+        let hfov = 2.0 * (width / 2.0 / distance).atan().to_degrees();
+        let tele = lens.zoom_position(lens.max_magnification()).unwrap_or(0);
+        let hfov = hfov.clamp(lens.hfov(tele), lens.hfov(0));
+        lens.zoom_position_for_hfov(hfov).unwrap_or(0)
+    }
+
+    /// The image flip and pan/tilt reverse settings this pose assumes
+    pub fn setup_commands(&self) -> Vec<Command> {
+        vec![
+            System::ImgFlipOnOff(OnOff::from(self.image_flip)).into(),
+            System::PanReverseOnOFf(OnOff::from(self.pan_reverse)).into(),
+            System::TiltReverseOnOff(OnOff::from(self.tilt_reverse)).into(),
+        ]
+    }
+}
+
+impl Pose {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+// Saved as `key = value` lines, like `CameraState`
+impl fmt::Display for Pose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "x = {}", self.position.x)?;
+        writeln!(f, "y = {}", self.position.y)?;
+        writeln!(f, "z = {}", self.position.z)?;
+        writeln!(f, "yaw = {}", self.yaw)?;
+        writeln!(f, "pitch = {}", self.pitch)?;
+        writeln!(f, "roll = {}", self.roll)?;
+        writeln!(f, "ceiling = {}", self.ceiling)?;
+        writeln!(f, "image_flip = {}", self.image_flip)?;
+        writeln!(f, "pan_reverse = {}", self.pan_reverse)?;
+        writeln!(f, "tilt_reverse = {}", self.tilt_reverse)
+    }
+}
+
+impl FromStr for Pose {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut pose = Pose::default();
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or(ViscaError::ParameterOutOfRange("pose line"))?;
+            let value = value.trim();
+            let number = || value.parse::<f32>().map_err(|_| ViscaError::ParameterOutOfRange("pose value"));
+            let flag = || value.parse::<bool>().map_err(|_| ViscaError::ParameterOutOfRange("pose value"));
+            match key.trim() {
+                "x" => pose.position.x = number()?,
+                "y" => pose.position.y = number()?,
+                "z" => pose.position.z = number()?,
+                "yaw" => pose.yaw = number()?,
+                "pitch" => pose.pitch = number()?,
+                "roll" => pose.roll = number()?,
+                "ceiling" => pose.ceiling = flag()?,
+                "image_flip" => pose.image_flip = flag()?,
+                "pan_reverse" => pose.pan_reverse = flag()?,
+                "tilt_reverse" => pose.tilt_reverse = flag()?,
+                _ => return Err(ViscaError::ParameterOutOfRange("pose key")),
+            }
+        }
+        Ok(pose)
+    }
+}
+
+fn rotate_x([x, y, z]: [f32; 3], degrees: f32) -> [f32; 3] {
+    let (s, c) = degrees.to_radians().sin_cos();
+    [x, y * c - z * s, y * s + z * c]
+}
+
+// Positive angles raise x towards z
+fn rotate_y([x, y, z]: [f32; 3], degrees: f32) -> [f32; 3] {
+    let (s, c) = degrees.to_radians().sin_cos();
+    [x * c - z * s, y, x * s + z * c]
+}
+
+fn rotate_z([x, y, z]: [f32; 3], degrees: f32) -> [f32; 3] {
+    let (s, c) = degrees.to_radians().sin_cos();
+    [x * c - y * s, x * s + y * c, z]
+}
+
+impl Camera {
+    /// Points the camera at `target`, and with `framing` also zooms so that
+    /// the given width in metres fills the picture at the target
+    pub fn look_at(
+        &mut self,
+        pose: &Pose,
+        target: Point,
+        speed: PanTiltSpeed,
+        framing: Option<(&Lens, f32)>,
+    ) -> Result<()> {
+        let (pan, tilt) = pose.aim(target);
+        let pan_tilt = PanTilt::AbsolutePos(speed, motion::pan_position(pan), motion::tilt_position(tilt));
+        match framing {
+            Some((lens, width)) => {
+                // Both at once, so the zoom happens during the move
+                let zoom = self.submit(Zoom::Direct(pose.framing(lens, target, width)))?;
+                self.execute(pan_tilt, MOVE_TIMEOUT)?;
+                self.wait(zoom, MOVE_TIMEOUT).map(|_| ())
+            }
+            None => self.execute(pan_tilt, MOVE_TIMEOUT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sighting {
+    pub target: Point,
+    pub pan: f32, // degrees, as read from the camera
+    pub tilt: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub pose: Pose,
+    pub rms_error: f32, // degrees, between the sightings and where the pose aims
+}
+
+impl Camera {
+    /// Records where the camera points now as a sighting of `target`
+    pub fn sight(&mut self, target: Point) -> Result<Sighting> {
+        let position = self.position()?;
+        Ok(Sighting {
+            target,
+            pan: motion::pan_degrees(position.pan),
+            tilt: motion::tilt_degrees(position.tilt),
+        })
+    }
+}
+
+/// Solves for the pose of a camera from its sightings. `mount` supplies the
+/// ceiling, image flip and reverse settings; its position and angles are
+/// ignored.
+pub fn calibrate(sightings: &[Sighting], mount: &Pose) -> Result<Calibration> {
+    if sightings.len() < 3 {
+        return Err(ViscaError::ParameterOutOfRange("sightings"));
+    }
+    let n = sightings.len() as f32;
+    let centre = sightings.iter().fold(Point::default(), |c, s| {
+        Point::new(c.x + s.target.x / n, c.y + s.target.y / n, c.z + s.target.z / n)
+    });
+    let spread = sightings.iter().map(|s| centre.distance(s.target)).fold(1.0, f32::max);
+
+    let mut best: Option<([f32; 6], f32)> = None;
+    for distance in [spread, 3.0 * spread] {
+        for height in [-spread, 0.0, spread] {
+            for step in 0..8 {
+                let bearing = (step as f32 * 45.0).to_radians();
+                let x = centre.x - distance * bearing.cos();
+                let y = centre.y - distance * bearing.sin();
+                // Pointing at the centre of the points, less the average pan
+                let mean_pan = sightings.iter().map(|s| s.pan).sum::<f32>() / n;
+                let yaw = bearing.to_degrees() - mean_pan;
+                let fit = least_squares(sightings, mount, [x, y, centre.z + height, yaw, 0.0, 0.0]);
+                if best.is_none_or(|(_, cost)| fit.1 < cost) {
+                    best = Some(fit);
+                }
+            }
+        }
+    }
+
+    let (params, cost) = best.unwrap_or_default();
+    Ok(Calibration {
+        pose: with_params(mount, &params),
+        rms_error: (cost / (2.0 * n)).sqrt(),
+    })
+}
+
+fn with_params(mount: &Pose, p: &[f32; 6]) -> Pose {
+    Pose {
+        position: Point::new(p[0], p[1], p[2]),
+        yaw: (p[3] + 180.0).rem_euclid(360.0) - 180.0,
+        pitch: p[4],
+        roll: p[5],
+        ..*mount
+    }
+}
+
+// Differences in degrees between where `pose` aims and each sighting
+fn residuals(sightings: &[Sighting], pose: &Pose) -> Vec<f32> {
+    let wrap = |a: f32| (a + 180.0).rem_euclid(360.0) - 180.0;
+    sightings
+        .iter()
+        .flat_map(|s| {
+            let (pan, tilt) = pose.aim(s.target);
+            [wrap(pan - s.pan), tilt - s.tilt]
+        })
+        .collect()
+}
+
+// Levenberg-Marquardt from `start`, returning the parameters and the sum of
+// squared residuals
+fn least_squares(sightings: &[Sighting], mount: &Pose, start: [f32; 6]) -> ([f32; 6], f32) {
+    const STEP: f32 = 1e-2;
+    let cost = |r: &[f32]| r.iter().map(|v| v * v).sum::<f32>();
+    let mut params = start;
+    let mut r = residuals(sightings, &with_params(mount, &params));
+    let mut current = cost(&r);
+    let mut lambda = 1e-2;
+
+    for _ in 0..200 {
+        // Jacobian by forward differences, one column per parameter
+        let columns: Vec<Vec<f32>> = (0..6)
+            .map(|i| {
+                let mut moved = params;
+                moved[i] += STEP;
+                let r2 = residuals(sightings, &with_params(mount, &moved));
+                r2.iter().zip(&r).map(|(a, b)| (a - b) / STEP).collect()
+            })
+            .collect();
+        let mut a = [[0.0f32; 6]; 6];
+        let mut g = [0.0f32; 6];
+        for i in 0..6 {
+            for j in 0..6 {
+                a[i][j] = columns[i].iter().zip(&columns[j]).map(|(x, y)| x * y).sum();
+            }
+            g[i] = -columns[i].iter().zip(&r).map(|(x, y)| x * y).sum::<f32>();
+        }
+        for (i, row) in a.iter_mut().enumerate() {
+            row[i] *= 1.0 + lambda;
+        }
+
+        let Some(delta) = solve(a, g) else { break };
+        let mut next = params;
+        next.iter_mut().zip(delta).for_each(|(p, d)| *p += d);
+        let r2 = residuals(sightings, &with_params(mount, &next));
+        let next_cost = cost(&r2);
+        if next_cost < current {
+            let converged = current - next_cost < 1e-9 * current.max(1e-9);
+            params = next;
+            r = r2;
+            current = next_cost;
+            lambda = (lambda / 3.0).max(1e-7);
+            if converged {
+                break;
+            }
+        } else {
+            lambda *= 3.0;
+            if lambda > 1e7 {
+                break;
+            }
+        }
+    }
+    (params, current)
+}
+
+// Gaussian elimination with partial pivoting
+fn solve(mut a: [[f32; 6]; 6], mut b: [f32; 6]) -> Option<[f32; 6]> {
+    for col in 0..6 {
+        let pivot = (col..6).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..6 {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (k, v) in a[row].iter_mut().enumerate().skip(col) {
+                *v -= factor * pivot_row[k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0f32; 6];
+    for row in (0..6).rev() {
+        let sum: f32 = (row + 1..6).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sightings(pose: &Pose) -> Vec<Sighting> {
+        [
+            Point::new(5.0, 0.0, 1.0),
+            Point::new(6.0, 3.0, 1.5),
+            Point::new(4.0, -2.5, 0.5),
+            Point::new(7.0, 1.0, 2.5),
+            Point::new(5.5, -1.0, 0.0),
+        ]
+        .into_iter()
+        .map(|target| {
+            let (pan, tilt) = pose.aim(target);
+            Sighting { target, pan, tilt }
+        })
+        .collect()
+    }
+
+    fn assert_recovers(pose: Pose) {
+        let calibration = calibrate(&sightings(&pose), &pose).unwrap();
+        let found = calibration.pose;
+        assert!(calibration.rms_error < 0.05, "rms error {}", calibration.rms_error);
+        assert!(found.position.distance(pose.position) < 0.05, "position {:?}", found.position);
+        for (a, b) in [(found.yaw, pose.yaw), (found.pitch, pose.pitch), (found.roll, pose.roll)] {
+            assert!((a - b).abs() < 0.5, "angles {:?}", (found.yaw, found.pitch, found.roll));
+        }
+    }
+
+    #[test]
+    fn aim_straight_ahead_is_zero() {
+        let pose = Pose { yaw: 90.0, ..Pose::default() };
+        let (pan, tilt) = pose.aim(Point::new(0.0, 4.0, 0.0));
+        assert!(pan.abs() < 1e-3 && tilt.abs() < 1e-3);
+    }
+
+    #[test]
+    fn calibrate_recovers_pose() {
+        assert_recovers(Pose {
+            position: Point::new(0.5, 0.8, 2.2),
+            yaw: 10.0,
+            pitch: -3.0,
+            roll: 2.0,
+            ..Pose::default()
+        });
+    }
+
+    #[test]
+    fn calibrate_recovers_ceiling_pose() {
+        assert_recovers(Pose {
+            position: Point::new(-1.0, 0.0, 3.0),
+            yaw: -15.0,
+            ceiling: true,
+            ..Pose::default()
+        });
+    }
+
+    #[test]
+    fn calibrate_needs_three_sightings() {
+        let pose = Pose::default();
+        assert!(calibrate(&sightings(&pose)[..2], &pose).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+// Connection health monitoring.
+//
+// A background thread sends a power inquiry every interval from a socket of
+// its own, so its replies never mix with those the `Camera` is waiting for,
+// and grades the answers: a quick reply is `Online`, a slow or failed one, or
+// a few missed, `Degraded`, and enough misses in a row `Offline`. A camera
+// that answers again after being offline has restarted and expects sequence
+// numbers from zero, so they are reset; it is `Rebooting` until it answers
+// the inquiry normally. The camera's socket needs no reconnecting, UDP keeps
+// no connection to lose. Each restart is counted, and the `Camera` checks the
+// count whenever it sends or receives, dropping what the camera has forgotten:
+// commands in flight, cached replies and the geofence's tracked position.
+// Changes of state go to listeners on the heartbeat thread, called without
+// any lock held so they may use the `Camera`'s heartbeat methods.
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::inquiry;
+use crate::messages::{Packet, Reply, ViscaMessage};
+use crate::{send_packet, MessageType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HealthState {
+    Online,
+    Degraded,  // answering slowly, with errors, or missing beats
+    Offline,   // `HeartbeatConfig::misses` beats missed in a row
+    Rebooting, // back from offline but not yet answering normally
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthChange {
+    pub previous: HealthState,
+    pub current: HealthState,
+    pub round_trip: Option<Duration>, // of the beat that caused the change, None if missed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeartbeatConfig {
+    pub interval: Duration,
+    pub timeout: Duration, // a beat not answered in this long is missed
+    pub slow: Duration,    // answers slower than this are degraded
+    pub misses: u32,       // missed in a row to be offline
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        HeartbeatConfig {
+            interval: Duration::from_secs(2),
+            timeout: Duration::from_millis(500),
+            slow: Duration::from_millis(200),
+            misses: 3,
+        }
+    }
+}
+
+type Listener = Box<dyn FnMut(&HealthChange) -> bool + Send>; // false to be removed
+
+struct Shared {
+    state: HealthState,
+    listeners: Vec<Listener>,
+    shutdown: bool,
+}
+
+pub(crate) struct Heartbeat {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    restarts: Arc<AtomicU64>, // counted by the thread
+    seen: u64,                // restarts already handled by the `Camera`
+    thread: Option<JoinHandle<()>>,
+}
+
+// What came back from one power inquiry
+enum Beat {
+    Answered(Duration),
+    Refused(Duration), // error reply
+    SequenceError,     // the camera has restarted and rejects our numbering
+    Missed,
+}
+
+impl Heartbeat {
+    pub fn spawn(socket: UdpSocket, seqnum: Arc<AtomicU32>, config: HeartbeatConfig) -> Self {
+        let shared = Arc::new((
+            Mutex::new(Shared { state: HealthState::Online, listeners: Vec::new(), shutdown: false }),
+            Condvar::new(),
+        ));
+        let state = Arc::clone(&shared);
+        let restarts = Arc::new(AtomicU64::new(0));
+        let restarted = Arc::clone(&restarts);
+
+        let thread = thread::spawn(move || {
+            let mut missed = 0;
+            let restart = || {
+                reset_seqnum(&socket, &seqnum);
+                restarted.fetch_add(1, Ordering::SeqCst);
+            };
+            loop {
+                let beat = beat(&socket, &seqnum, config.timeout);
+                let (lock, condvar) = &*state;
+                let mut shared = lock.lock().unwrap();
+                if shared.shutdown {
+                    return;
+                }
+
+                let previous = shared.state;
+                let (current, round_trip) = match beat {
+                    Beat::Answered(t) | Beat::Refused(t) if previous == HealthState::Offline => {
+                        restart();
+                        match beat {
+                            Beat::Answered(_) if t <= config.slow => (HealthState::Online, Some(t)),
+                            _ => (HealthState::Rebooting, Some(t)),
+                        }
+                    }
+                    Beat::SequenceError => {
+                        restart();
+                        (HealthState::Rebooting, None)
+                    }
+                    Beat::Answered(t) if t <= config.slow => (HealthState::Online, Some(t)),
+                    Beat::Answered(t) if previous == HealthState::Rebooting => (HealthState::Online, Some(t)),
+                    Beat::Answered(t) => (HealthState::Degraded, Some(t)),
+                    Beat::Refused(t) if previous == HealthState::Rebooting => (HealthState::Rebooting, Some(t)),
+                    Beat::Refused(t) => (HealthState::Degraded, Some(t)),
+                    Beat::Missed if missed + 1 >= config.misses => (HealthState::Offline, None),
+                    Beat::Missed if previous == HealthState::Offline => (HealthState::Offline, None),
+                    Beat::Missed => (HealthState::Degraded, None),
+                };
+                missed = if matches!(beat, Beat::Missed) { missed + 1 } else { 0 };
+
+                if current != previous {
+                    shared.state = current;
+                    let mut listeners = std::mem::take(&mut shared.listeners);
+                    drop(shared);
+                    let change = HealthChange { previous, current, round_trip };
+                    listeners.retain_mut(|listener| listener(&change));
+                    shared = lock.lock().unwrap();
+                    // Keep any listeners added meanwhile, after the others
+                    listeners.append(&mut shared.listeners);
+                    shared.listeners = listeners;
+                    if shared.shutdown {
+                        return;
+                    }
+                }
+                let shared = condvar.wait_timeout(shared, config.interval).unwrap().0;
+                if shared.shutdown {
+                    return;
+                }
+            }
+        });
+
+        Heartbeat { shared, restarts, seen: 0, thread: Some(thread) }
+    }
+
+    pub fn state(&self) -> HealthState {
+        self.shared.0.lock().unwrap().state
+    }
+
+    pub fn listen(&self, listener: Listener) {
+        self.shared.0.lock().unwrap().listeners.push(listener);
+    }
+
+    // Whether the camera has restarted since this was last asked
+    pub fn restarted(&mut self) -> bool {
+        let restarts = self.restarts.load(Ordering::SeqCst);
+        let restarted = restarts != self.seen;
+        self.seen = restarts;
+        restarted
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shared;
+        lock.lock().unwrap().shutdown = true;
+        condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn beat(socket: &UdpSocket, seqnum: &AtomicU32, timeout: Duration) -> Beat {
+    let start = Instant::now();
+    let bytes = inquiry::System::Power.bytes();
+    let Ok(sent) = send_packet(socket, seqnum, MessageType::Inquiry, &bytes) else { return Beat::Missed };
+
+    let mut buf = [0u8; 1500];
+    loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            return Beat::Missed;
+        }
+        // An unreachable camera shows up as an error here straight away,
+        // wait the timeout out so a beat is only counted once
+        let Ok(received) = socket.recv(&mut buf) else {
+            thread::sleep(timeout.saturating_sub(start.elapsed()));
+            return Beat::Missed;
+        };
+        let Some(packet) = Packet::parse(&buf[..received]) else { continue };
+        match (packet.msg_type, Reply::parse(packet.payload)) {
+            (MessageType::ControlReply, _) if packet.payload == [0x0F, 0x01] => return Beat::SequenceError,
+            (MessageType::Reply, Some(Reply::Completion(..))) if packet.seqnum == sent => {
+                return Beat::Answered(start.elapsed())
+            }
+            (MessageType::Reply, Some(Reply::Error(..))) if packet.seqnum == sent => {
+                return Beat::Refused(start.elapsed())
+            }
+            _ => {} // a late reply to an earlier beat
+        }
+    }
+}
+
+// Sequence numbers start again from zero with the reset itself
+fn reset_seqnum(socket: &UdpSocket, seqnum: &AtomicU32) {
+    seqnum.store(0, Ordering::SeqCst);
+    let _ = send_packet(socket, seqnum, MessageType::ControlCommand, &[0x01]);
+}
+
+// Forwards changes to a channel, until the receiver is dropped
+pub(crate) fn channel() -> (Listener, Receiver<HealthChange>) {
+    let (sender, receiver) = mpsc::channel();
+    (Box::new(move |change: &HealthChange| sender.send(*change).is_ok()), receiver)
+}
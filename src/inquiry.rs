@@ -1,8 +1,13 @@
 #![allow(dead_code)]
 
 use crate::messages::*;
+use crate::params::{
+    BlackGammaRange, ChromaSuppress, DetailBandwidth, DetailMode, FrameRate, Gain, GainLimit, GainPointPosition,
+    GammaMode, Iris, KneeMode, Matrix, PresetNumber, Shutter, WBMode,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Exposure {
     Mode,
     Iris,
@@ -10,9 +15,9 @@ pub enum Exposure {
     GainLimit,
     GainPoint,
     GainPointPosition,
-    Shutter,
-    MaxShutter,
-    MinShutter,
+    Shutter(FrameRate), // speeds are reported for this video format family
+    MaxShutter(FrameRate),
+    MinShutter(FrameRate),
     AESpeed,
     ExpCompOnOff,
     ExpCompLevel,
@@ -34,9 +39,9 @@ impl ViscaMessage for Exposure {
             Exposure::GainLimit => vec![0x81, 0x09, 0x04, 0x2C, 0xFF],
             Exposure::GainPoint => vec![0x81, 0x09, 0x05, 0x0C, 0xFF],
             Exposure::GainPointPosition => vec![0x81, 0x09, 0x05, 0x4C, 0xFF],
-            Exposure::Shutter => vec![0x81, 0x09, 0x04, 0x4A, 0xFF],
-            Exposure::MaxShutter => vec![0x81, 0x09, 0x05, 0x2A, 0x00, 0xFF],
-            Exposure::MinShutter => vec![0x81, 0x09, 0x05, 0x2A, 0x01, 0xFF],
+            Exposure::Shutter(_) => vec![0x81, 0x09, 0x04, 0x4A, 0xFF],
+            Exposure::MaxShutter(_) => vec![0x81, 0x09, 0x05, 0x2A, 0x00, 0xFF],
+            Exposure::MinShutter(_) => vec![0x81, 0x09, 0x05, 0x2A, 0x01, 0xFF],
             Exposure::AESpeed => vec![0x81, 0x09, 0x04, 0x5D, 0xFF],
             Exposure::ExpCompOnOff => vec![0x81, 0x09, 0x04, 0x3E, 0xFF],
             Exposure::ExpCompLevel => vec![0x81, 0x09, 0x04, 0x4E, 0xFF],
@@ -73,19 +78,29 @@ impl ViscaInquiry for Exposure {
             },
             Exposure::Iris => {
                 match bytes {
-                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => format!("Iris: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => match Iris::new(merge_u8(*p1, *p2)) {
+                        Ok(iris) => format!("Iris: {}", iris),
+                        Err(_) => format!("Iris: {:02X}", merge_u8(*p1, *p2)),
+                    },
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
             Exposure::Gain => {
                 match bytes {
-                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => format!("Gain: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => match Gain::new(merge_u8(*p1, *p2)) {
+                        Ok(gain) => format!("Gain: {}", gain),
+                        Err(_) => format!("Gain: {:02X}", merge_u8(*p1, *p2)),
+                    },
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
             Exposure::GainLimit => {
                 match bytes {
-                    [SOCK, 0x50, p, 0xFF] => format!("Gain Limit: {:02X}", *p),
+                    [SOCK, 0x50, 0xFF, 0xFF] => format!("Gain Limit: {}", GainLimit::OFF),
+                    [SOCK, 0x50, p, 0xFF] => match GainLimit::new(*p) {
+                        Ok(limit) => format!("Gain Limit: {}", limit),
+                        Err(_) => format!("Gain Limit: {:02X}", *p),
+                    },
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
@@ -98,25 +113,28 @@ impl ViscaInquiry for Exposure {
             },
             Exposure::GainPointPosition => {
                 match bytes {
-                    [SOCK, 0x50, p1, p2, 0xFF] => format!("Gain Point Position: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, p1, p2, 0xFF] => match GainPointPosition::new(merge_u8(*p1, *p2)) {
+                        Ok(position) => format!("Gain Point Position: {}", position),
+                        Err(_) => format!("Gain Point Position: {:02X}", merge_u8(*p1, *p2)),
+                    },
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
-            Exposure::Shutter => {
+            Exposure::Shutter(rate) => {
                 match bytes {
-                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => format!("Shutter: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, 0x00, 0x00, p1, p2, 0xFF] => format!("Shutter: {}", shutter_label(merge_u8(*p1, *p2), *rate)),
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
-            Exposure::MaxShutter => {
+            Exposure::MaxShutter(rate) => {
                 match bytes {
-                    [SOCK, 0x50, p1, p2, 0xFF] => format!("Max Shutter: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, p1, p2, 0xFF] => format!("Max Shutter: {}", shutter_label(merge_u8(*p1, *p2), *rate)),
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
-            Exposure::MinShutter => {
+            Exposure::MinShutter(rate) => {
                 match bytes {
-                    [SOCK, 0x50, p1, p2, 0xFF] => format!("Min Shutter: {:02X}", merge_u8(*p1, *p2)),
+                    [SOCK, 0x50, p1, p2, 0xFF] => format!("Min Shutter: {}", shutter_label(merge_u8(*p1, *p2), *rate)),
                     x => format!("Unknown reply: {:?}", x),
                 }
             },
@@ -185,4 +203,486 @@ impl ViscaInquiry for Exposure {
             },
         }
     }
-}
\ No newline at end of file
+
+    // The visibility enhancer reports on as 06, returned as the `OnOff` code
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Exposure::VisEnhanceOnOff => match byte(bytes)? {
+                0x06 => Some(0x02),
+                v => Some(v),
+            },
+            Exposure::VisEnhanceLevel => match bytes {
+                [0x90, 0x50, 0x00, p, q, r, 0x00, 0x00, 0x00, 0x00, 0xFF] => {
+                    Some(((*p as u32) << 8) | ((*q as u32) << 4) | *r as u32)
+                }
+                _ => None,
+            },
+            Exposure::Mode
+            | Exposure::GainLimit
+            | Exposure::GainPoint
+            | Exposure::AESpeed
+            | Exposure::ExpCompOnOff
+            | Exposure::BackLight
+            | Exposure::SpotLight
+            | Exposure::LowLightBasisBrightnessOnOff
+            | Exposure::LowLightBasisBrightnessLevel => byte(bytes),
+            Exposure::NDFilter => None,
+            _ => nibbles(bytes),
+        }
+    }
+}
+
+// Parameter of a `y0 50 ... FF` reply where every byte holds one nibble
+fn nibbles(bytes: &[u8]) -> Option<u32> {
+    match bytes {
+        [0x90, 0x50, params @ .., 0xFF] if !params.is_empty() && params.len() <= 8 => {
+            params.iter().try_fold(0u32, |acc, &b| (b <= 0x0F).then_some((acc << 4) | b as u32))
+        }
+        _ => None,
+    }
+}
+
+// Parameter of a `y0 50 pp FF` reply holding a whole byte
+fn byte(bytes: &[u8]) -> Option<u32> {
+    match bytes {
+        [0x90, 0x50, p, 0xFF] => Some(*p as u32),
+        _ => None,
+    }
+}
+
+fn unknown(bytes: &[u8]) -> String {
+    format!("Unknown reply: {:?}", bytes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Colour {
+    WBMode,
+    RGain,
+    BGain,
+    Speed,
+    ChromaSuppress,
+    Matrix,
+    Level,
+    Phase,
+    RG,
+    RB,
+    GR,
+    GB,
+    BR,
+    BG,
+}
+
+impl ViscaMessage for Colour {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Colour::WBMode => vec![0x81, 0x09, 0x04, 0x35, 0xFF],
+            Colour::RGain => vec![0x81, 0x09, 0x04, 0x43, 0xFF],
+            Colour::BGain => vec![0x81, 0x09, 0x04, 0x44, 0xFF],
+            Colour::Speed => vec![0x81, 0x09, 0x04, 0x56, 0xFF],
+            Colour::ChromaSuppress => vec![0x81, 0x09, 0x04, 0x5F, 0xFF],
+            Colour::Matrix => vec![0x81, 0x09, 0x7E, 0x01, 0x3D, 0xFF],
+            Colour::Level => vec![0x81, 0x09, 0x04, 0x49, 0xFF],
+            Colour::Phase => vec![0x81, 0x09, 0x04, 0x4F, 0xFF],
+            Colour::RG => vec![0x81, 0x09, 0x7E, 0x01, 0x7A, 0xFF],
+            Colour::RB => vec![0x81, 0x09, 0x7E, 0x01, 0x7B, 0xFF],
+            Colour::GR => vec![0x81, 0x09, 0x7E, 0x01, 0x7C, 0xFF],
+            Colour::GB => vec![0x81, 0x09, 0x7E, 0x01, 0x7D, 0xFF],
+            Colour::BR => vec![0x81, 0x09, 0x7E, 0x01, 0x7E, 0xFF],
+            Colour::BG => vec![0x81, 0x09, 0x7E, 0x01, 0x7F, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Colour {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        let Some(val) = self.parse_value(bytes) else { return unknown(bytes) };
+        match self {
+            Colour::WBMode => match WBMode::from_value(val as u8) {
+                Ok(mode) => format!("WB Mode: {:?}", mode),
+                Err(_) => unknown(bytes),
+            },
+            Colour::RGain => format!("R Gain: {:+}", val as i32 - 0x80),
+            Colour::BGain => format!("B Gain: {:+}", val as i32 - 0x80),
+            Colour::Speed => format!("WB Speed: {}", val),
+            Colour::ChromaSuppress => match ChromaSuppress::from_value(val as u8) {
+                Ok(level) => format!("Chroma Suppress: {:?}", level),
+                Err(_) => unknown(bytes),
+            },
+            Colour::Matrix => match Matrix::from_value(val as u8) {
+                Ok(matrix) => format!("Matrix: {:?}", matrix),
+                Err(_) => unknown(bytes),
+            },
+            Colour::Level => format!("Colour Level: {}", val),
+            Colour::Phase => format!("Colour Phase: {:+} degrees", (val as i32 - 7) * 2),
+            Colour::RG | Colour::RB | Colour::GR | Colour::GB | Colour::BR | Colour::BG => {
+                format!("Matrix {:?}: {:+}", self, val as i32 - 0x63)
+            }
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Colour::WBMode | Colour::Speed | Colour::ChromaSuppress | Colour::Matrix => byte(bytes),
+            _ => nibbles(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Detail {
+    Level,
+    Mode,
+    Bandwidth,
+    Crispening,
+    HVBalance,
+    BWBalance,
+    Limit,
+    HighlightDetail,
+    SuperLow,
+}
+
+impl ViscaMessage for Detail {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Detail::Level => vec![0x81, 0x09, 0x04, 0x42, 0xFF],
+            Detail::Mode => vec![0x81, 0x09, 0x05, 0x42, 0x01, 0xFF],
+            Detail::Bandwidth => vec![0x81, 0x09, 0x05, 0x42, 0x02, 0xFF],
+            Detail::Crispening => vec![0x81, 0x09, 0x05, 0x42, 0x03, 0xFF],
+            Detail::HVBalance => vec![0x81, 0x09, 0x05, 0x42, 0x04, 0xFF],
+            Detail::BWBalance => vec![0x81, 0x09, 0x05, 0x42, 0x05, 0xFF],
+            Detail::Limit => vec![0x81, 0x09, 0x05, 0x42, 0x06, 0xFF],
+            Detail::HighlightDetail => vec![0x81, 0x09, 0x05, 0x42, 0x07, 0xFF],
+            Detail::SuperLow => vec![0x81, 0x09, 0x05, 0x42, 0x08, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Detail {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        let Some(val) = self.parse_value(bytes) else { return unknown(bytes) };
+        match self {
+            Detail::Level => format!("Detail Level: {:+}", val as i32 - 7),
+            Detail::Mode => match DetailMode::from_value(val as u8) {
+                Ok(mode) => format!("Detail Mode: {:?}", mode),
+                Err(_) => unknown(bytes),
+            },
+            Detail::Bandwidth => match DetailBandwidth::from_value(val as u8) {
+                Ok(bandwidth) => format!("Detail Bandwidth: {:?}", bandwidth),
+                Err(_) => unknown(bytes),
+            },
+            Detail::HVBalance => format!("Detail HV Balance: {:+}", val as i32 - 7),
+            _ => format!("Detail {:?}: {}", self, val),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Detail::Level => nibbles(bytes),
+            _ => byte(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Knee {
+    Setting,
+    Mode,
+    Slope,
+    Point,
+}
+
+impl ViscaMessage for Knee {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Knee::Setting => vec![0x81, 0x09, 0x7E, 0x01, 0x6D, 0xFF],
+            Knee::Mode => vec![0x81, 0x09, 0x7E, 0x01, 0x54, 0xFF],
+            Knee::Slope => vec![0x81, 0x09, 0x7E, 0x01, 0x6F, 0xFF],
+            Knee::Point => vec![0x81, 0x09, 0x7E, 0x01, 0x6E, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Knee {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        let Some(val) = self.parse_value(bytes) else { return unknown(bytes) };
+        match self {
+            Knee::Setting => match val {
+                0x02 => "Knee Setting ON".to_string(),
+                0x03 => "Knee Setting OFF".to_string(),
+                _ => unknown(bytes),
+            },
+            Knee::Mode => match KneeMode::from_value(val as u8) {
+                Ok(mode) => format!("Knee Mode: {:?}", mode),
+                Err(_) => unknown(bytes),
+            },
+            Knee::Slope => format!("Knee Slope: {:+}", val as i32 - 7),
+            Knee::Point => format!("Knee Point: {}", val),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Knee::Setting | Knee::Mode => byte(bytes),
+            Knee::Slope | Knee::Point => nibbles(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gamma {
+    Mode,
+    Pattern,
+    Offset,
+    Level,
+    BlackGammaLevel,
+    BlackGammaRange,
+    BlackLevel,
+}
+
+impl ViscaMessage for Gamma {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Gamma::Mode => vec![0x81, 0x09, 0x04, 0x5B, 0xFF],
+            Gamma::Pattern => vec![0x81, 0x09, 0x05, 0x5B, 0xFF],
+            Gamma::Offset => vec![0x81, 0x09, 0x04, 0x1E, 0xFF],
+            Gamma::Level => vec![0x81, 0x09, 0x7E, 0x01, 0x71, 0xFF],
+            Gamma::BlackGammaLevel => vec![0x81, 0x09, 0x7E, 0x01, 0x72, 0xFF],
+            Gamma::BlackGammaRange => vec![0x81, 0x09, 0x7E, 0x01, 0x5A, 0xFF],
+            Gamma::BlackLevel => vec![0x81, 0x09, 0x04, 0x45, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Gamma {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        let Some(val) = self.parse_value(bytes) else { return unknown(bytes) };
+        match self {
+            Gamma::Mode => match GammaMode::from_value(val as u8) {
+                Ok(mode) => format!("Gamma: {:?}", mode),
+                Err(_) => unknown(bytes),
+            },
+            Gamma::Pattern => format!("Gamma Pattern: {}", val),
+            Gamma::Offset => format!("Gamma Offset: {:+}", val as i32 - 0x40),
+            Gamma::Level => format!("Gamma Level: {:+}", val as i32 - 7),
+            Gamma::BlackGammaLevel => format!("Black Gamma Level: {:+}", val as i32 - 7),
+            Gamma::BlackGammaRange => match BlackGammaRange::from_value(val as u8) {
+                Ok(range) => format!("Black Gamma Range: {:?}", range),
+                Err(_) => unknown(bytes),
+            },
+            Gamma::BlackLevel => format!("Black Level: {:+}", val as i32 - 0x30),
+        }
+    }
+
+    // The offset comes as a sign and a magnitude, `00 00 00 0s 0p 0q`, and is
+    // returned as the `GammaOffset` code
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Gamma::Mode | Gamma::BlackGammaRange => byte(bytes),
+            Gamma::Offset => match nibbles(bytes)? {
+                v if v & 0xF00 == 0 => Some(0x40 + (v & 0xFF)),
+                v => (0x40u32).checked_sub(v & 0xFF),
+            },
+            _ => nibbles(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Zoom {
+    Position,
+}
+
+impl ViscaMessage for Zoom {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Zoom::Position => vec![0x81, 0x09, 0x04, 0x47, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Zoom {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        match self.parse_value(bytes) {
+            Some(pos) => format!("Zoom Position: {:04X}", pos),
+            None => unknown(bytes),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        nibbles(bytes)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Focus {
+    Mode,
+    Position,
+}
+
+impl ViscaMessage for Focus {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Focus::Mode => vec![0x81, 0x09, 0x04, 0x38, 0xFF],
+            Focus::Position => vec![0x81, 0x09, 0x04, 0x48, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Focus {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        match (self, self.parse_value(bytes)) {
+            (Focus::Mode, Some(0x02)) => "Auto Focus".to_string(),
+            (Focus::Mode, Some(0x03)) => "Manual Focus".to_string(),
+            (Focus::Position, Some(pos)) => format!("Focus Position: {:04X}", pos),
+            _ => unknown(bytes),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        match self {
+            Focus::Mode => byte(bytes),
+            Focus::Position => nibbles(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PanTilt {
+    Position,
+}
+
+impl PanTilt {
+    // Reply is y0 50 0p 0p 0p 0p 0p 0t 0t 0t 0t FF, too wide for `parse_value`
+    pub fn parse_position(bytes: &[u8]) -> Option<(u32, u16)> {
+        match bytes {
+            [0x90, 0x50, p @ .., 0xFF] if p.len() == 9 && p.iter().all(|&b| b <= 0x0F) => {
+                let pan = p[..5].iter().fold(0u32, |acc, &b| (acc << 4) | b as u32);
+                let tilt = merge_u16(p[5], p[6], p[7], p[8]);
+                Some((pan, tilt))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ViscaMessage for PanTilt {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            PanTilt::Position => vec![0x81, 0x09, 0x06, 0x12, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for PanTilt {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        match PanTilt::parse_position(bytes) {
+            Some((pan, tilt)) => format!("Pan: {:05X}, Tilt: {:04X}", pan, tilt),
+            None => unknown(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Preset {
+    DriveSpeed(PresetNumber),
+}
+
+impl ViscaMessage for Preset {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Preset::DriveSpeed(p) => vec![0x81, 0x09, 0x7E, 0x01, 0x0B, p.value(), 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for Preset {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        match (self, self.parse_value(bytes)) {
+            (Preset::DriveSpeed(p), Some(speed)) => format!("Preset {} Drive Speed: {}", p.value(), speed),
+            _ => unknown(bytes),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        byte(bytes)
+    }
+}
+
+fn shutter_label(position: u8, rate: FrameRate) -> String {
+    match Shutter::new(position) {
+        Ok(shutter) => shutter.label(rate),
+        Err(_) => format!("{:02X}", position),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum System {
+    Power,
+}
+
+impl ViscaMessage for System {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            System::Power => vec![0x81, 0x09, 0x04, 0x00, 0xFF],
+        }
+    }
+
+    fn msg_type(&self) -> MessageType {
+        MessageType::Inquiry
+    }
+}
+
+impl ViscaInquiry for System {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        match (self, self.parse_value(bytes)) {
+            (System::Power, Some(0x02)) => "Power On".to_string(),
+            (System::Power, Some(0x03)) => "Power Standby".to_string(),
+            _ => unknown(bytes),
+        }
+    }
+
+    fn parse_value(&self, bytes: &[u8]) -> Option<u32> {
+        byte(bytes)
+    }
+}
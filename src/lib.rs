@@ -1,13 +1,39 @@
 // Visca over IP library based on datasheet found at:
 // https://www.sony.net/Products/CameraSystem/CA/BRC_X1000_BRC_H800/Technical_Document/C456100121.pdf
 
-use std::{io, io::Read, net::UdpSocket, time::Duration};
+use std::{io, io::Read, net::UdpSocket, time::{Duration, Instant}};
+use std::sync::{Arc, atomic::{AtomicU32, Ordering}, mpsc::Receiver};
 
-use messages::{ViscaCommand, MessageType};
+use messages::Packet;
+pub use messages::{MessageType, ViscaCommand, ViscaInquiry, ViscaMessage};
+pub use cache::{CacheConfig, CacheSource, Cached};
+pub use events::{CameraEvent, PollIntervals};
+pub use heartbeat::{HealthChange, HealthState, HeartbeatConfig};
+pub use fleet::{CameraConfig, DefaultSpeeds, Drift, Fleet, Health, Inventory, Outcome, Report, Transport};
+pub use presets::{CameraPresets, PresetStore, SlotPreset};
+pub use queue::{Finished, Ticket};
+pub use state::{diff, CameraState, Difference};
 
+pub mod animation;
+mod cache;
 pub mod commands;
+pub mod drive;
+mod events;
+mod fleet;
+pub mod geometry;
+mod heartbeat;
 pub mod inquiry;
+pub mod limits;
 mod messages;
+pub mod motion;
+pub mod optics;
+pub mod params;
+mod presets;
+mod queue;
+pub mod recording;
+mod state;
+pub mod tour;
+mod watchdog;
 
 #[derive(Debug)]
 pub enum ViscaError {
@@ -18,6 +44,26 @@ pub enum ViscaError {
   CommandCanceledError,
   NoSocketError,
   CommandNotExecutableError,
+  CommandTimeoutError,
+  UnknownError(u8),
+  ParameterOutOfRange(&'static str),
+  UnexpectedReply(Vec<u8>),
+  Geofenced,
+}
+
+impl ViscaError {
+  // Error codes from the `90 6y ee FF` reply
+  fn from_code(code: u8) -> Self {
+    match code {
+      0x01 => ViscaError::MessageLengthError,
+      0x02 => ViscaError::SyntaxError,
+      0x03 => ViscaError::CommandBufferFullError,
+      0x04 => ViscaError::CommandCanceledError,
+      0x05 => ViscaError::NoSocketError,
+      0x41 => ViscaError::CommandNotExecutableError,
+      x => ViscaError::UnknownError(x),
+    }
+  }
 }
 
 impl std::error::Error for ViscaError{
@@ -30,6 +76,11 @@ impl std::error::Error for ViscaError{
         ViscaError::CommandCanceledError => None,
         ViscaError::NoSocketError => None,
         ViscaError::CommandNotExecutableError => None,
+        ViscaError::CommandTimeoutError => None,
+        ViscaError::UnknownError(_) => None,
+        ViscaError::ParameterOutOfRange(_) => None,
+        ViscaError::UnexpectedReply(_) => None,
+        ViscaError::Geofenced => None,
     }
   }
 }
@@ -44,65 +95,382 @@ impl std::fmt::Display for ViscaError {
             ViscaError::CommandCanceledError => write!(f, "VISCA command canceled"),
             ViscaError::NoSocketError => write!(f, "VISCA no socket error"),
             ViscaError::CommandNotExecutableError => write!(f, "VISCA command not executable"),
+            ViscaError::CommandTimeoutError => write!(f, "VISCA command timed out waiting for completion"),
+            ViscaError::UnknownError(code) => write!(f, "VISCA unknown error {:02X}", code),
+            ViscaError::ParameterOutOfRange(name) => write!(f, "{} parameter out of range", name),
+            ViscaError::UnexpectedReply(bytes) => write!(f, "VISCA reply could not be read: {:02X?}", bytes),
+            ViscaError::Geofenced => write!(f, "target is outside the soft limits or in a no-go zone"),
         }
     }
 }
 
 pub type Result<T> = std::result::Result<T, ViscaError>;
 
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
 
 
 pub struct Camera {
   socket: UdpSocket,
-  seqnum: u32,
+  seqnum: Arc<AtomicU32>,
   timeout: Option<Duration>,
+  queue: queue::CommandQueue,
+  watchdog: Option<watchdog::Watchdog>,
+  heartbeat: Option<heartbeat::Heartbeat>,
+  events: events::Events,
+  cache: Option<cache::StateCache>,
+  recorder: Option<recording::Recorder>,
+  fence: Option<limits::Fence>,
+  frame_rate: Option<params::FrameRate>,
 }
 
 impl Camera {
   pub fn new(addr: &str) -> Result<Self> {
     let socket = UdpSocket::bind("0.0.0.0:0")
-      .map_err(|e| ViscaError::CameraConnectionError(e))?;
+      .map_err(ViscaError::CameraConnectionError)?;
     socket.connect(addr)
-      .map_err(|e| ViscaError::CameraConnectionError(e))?;
-    Ok(Camera { socket, seqnum: 0 , timeout: None})
+      .map_err(ViscaError::CameraConnectionError)?;
+    Ok(Camera {
+      socket,
+      seqnum: Default::default(),
+      timeout: None,
+      queue: Default::default(),
+      watchdog: None,
+      heartbeat: None,
+      events: Default::default(),
+      cache: None,
+      recorder: None,
+      fence: None,
+      frame_rate: None,
+    })
   }
 
   pub fn set_timout(&mut self, timeout: Option<Duration>) {
     self.timeout = timeout;
   }
+
+  /// The video format family the camera runs at, which the shutter positions
+  /// in a `CameraState` depend on. Recorded by `snapshot` and used by
+  /// `restore` to convert shutter speeds from states read at another rate.
+  pub fn set_frame_rate(&mut self, rate: Option<params::FrameRate>) {
+    self.frame_rate = rate;
+  }
+
+  pub fn frame_rate(&self) -> Option<params::FrameRate> {
+    self.frame_rate
+  }
   
   pub fn reset_seqnum(&mut self) -> io::Result<()> {
     self.send_bytes(MessageType::ControlCommand, &[0x01])
 }
 
   pub fn set(&mut self, command: impl ViscaCommand) -> io::Result<()> {
-    self.send_bytes(command.msg_type(), &command.bytes())
+    self.check_restart();
+    let bytes = self.fence(command.bytes()).map_err(limits::into_io)?;
+    self.note_motion(&command);
+    self.send_bytes(command.msg_type(), &bytes)?;
+    if let Some(cache) = &mut self.cache {
+      cache.sent(&bytes);
+    }
+    if command.is_ptzf() {
+      self.record(bytes);
+    }
+    Ok(())
   }
 
-  pub fn send_bytes(&mut self, message_type: MessageType, bytes: &[u8]) -> io::Result<()> {
-    let payload_type: [u8; 2] = (message_type as u16).to_be_bytes();
-
-    let payload_length = bytes.len() as u16;
-    let payload_length = payload_length.to_be_bytes();
-    let seq: [u8; 4] = self.seqnum.to_be_bytes();
-
-    // TODO: simplify commands by using redundancies
-    // payload for all commands begins with 0x81, {0x01 or 0x04}
-    // payload for all inquiries begins with 0x09, {0x01 or 0x04}
-
-    let message = [
-      &payload_type,
-      &payload_length,
-      &seq[0..=1],
-      &seq[2..=3],
-      bytes,
-    ]
-    .concat();
-
-    self.socket.send(&message)?;
-    self.seqnum = self.seqnum.wrapping_add(1);
+  /// Enables the dead-man watchdog: if a continuous movement (`PanTilt::Left`,
+  /// `Zoom::TeleVar`, `Focus::FarVarSpeed`, ...) has been sent and neither another
+  /// movement nor `feed_watchdog` follows within `interval`, `PanTilt::Stop`,
+  /// `Zoom::Stop` and `Focus::Stop` are sent from a background thread.
+  /// The same stops are sent when the `Camera` is dropped.
+  pub fn enable_watchdog(&mut self, interval: Duration) -> Result<()> {
+    let socket = self.socket.try_clone()
+      .map_err(ViscaError::CameraConnectionError)?;
+    let seqnum = Arc::clone(&self.seqnum);
+    let stop = move || {
+      for bytes in watchdog::stop_bytes() {
+        send_packet(&socket, &seqnum, MessageType::Command, &bytes)?;
+      }
+      Ok(())
+    };
+    self.watchdog = Some(watchdog::Watchdog::spawn(interval, stop));
     Ok(())
-}
+  }
+
+  pub fn disable_watchdog(&mut self) {
+    self.watchdog = None;
+  }
+
+  /// Tells the watchdog the operator is still in control, e.g. while a joystick
+  /// is held steady and no new movement command is needed
+  pub fn feed_watchdog(&self) {
+    if let Some(watchdog) = &self.watchdog {
+      watchdog.feed();
+    }
+  }
+
+  /// Starts monitoring the connection: a power inquiry is sent every
+  /// `config.interval` from a socket of its own and the answers graded into a
+  /// `HealthState`. When the camera comes back after a restart, sequence
+  /// numbers are reset and commands in flight, cached replies and the
+  /// geofence's tracked position are dropped.
+  pub fn enable_heartbeat(&mut self, config: HeartbeatConfig) -> Result<()> {
+    let socket = self.side_socket()?;
+    self.heartbeat = Some(heartbeat::Heartbeat::spawn(socket, Arc::clone(&self.seqnum), config));
+    Ok(())
+  }
+
+  pub fn disable_heartbeat(&mut self) {
+    self.heartbeat = None;
+  }
+
+  /// The connection's health, None unless the heartbeat is enabled
+  pub fn health(&self) -> Option<HealthState> {
+    self.heartbeat.as_ref().map(|h| h.state())
+  }
+
+  /// A channel of health state changes, None unless the heartbeat is enabled
+  pub fn health_changes(&self) -> Option<Receiver<HealthChange>> {
+    let heartbeat = self.heartbeat.as_ref()?;
+    let (listener, receiver) = heartbeat::channel();
+    heartbeat.listen(listener);
+    Some(receiver)
+  }
+
+  /// Calls `listener` on the heartbeat thread on every health state change.
+  /// Returns false, dropping `listener`, if the heartbeat isn't enabled.
+  pub fn on_health_change(&self, mut listener: impl FnMut(&HealthChange) + Send + 'static) -> bool {
+    match &self.heartbeat {
+      Some(heartbeat) => {
+        heartbeat.listen(Box::new(move |change: &HealthChange| {
+          listener(change);
+          true
+        }));
+        true
+      }
+      None => false,
+    }
+  }
+
+  // Drops what the camera forgot if the heartbeat has seen it restart:
+  // commands in flight, which now fail as cancelled, cached replies and the
+  // geofence's tracked position
+  fn check_restart(&mut self) {
+    if !self.heartbeat.as_mut().is_some_and(|h| h.restarted()) {
+      return;
+    }
+    for done in self.queue.restarted() {
+      self.events.command_done(&done);
+    }
+    self.clear_cache();
+    self.forget_fenced_position();
+  }
+
+  /// Returns a channel of `CameraEvent`s. The first subscription starts
+  /// polling the camera at the `PollIntervals` set, from a socket of its own.
+  /// Commands are reported as their replies are read by `poll`, `wait` or
+  /// `execute`; those sent with `set` are not tracked.
+  pub fn subscribe(&mut self) -> Result<Receiver<CameraEvent>> {
+    if !self.events.is_polling() {
+      let socket = self.side_socket()?;
+      self.events.start(socket, Arc::clone(&self.seqnum));
+    }
+    Ok(self.events.subscribe())
+  }
+
+  /// Changes how often `subscribe` polls. If polling has started it is
+  /// restarted, and the current values are reported again.
+  pub fn set_poll_intervals(&mut self, intervals: PollIntervals) -> Result<()> {
+    self.events.set_intervals(intervals);
+    if self.events.is_polling() {
+      let socket = self.side_socket()?;
+      self.events.start(socket, Arc::clone(&self.seqnum));
+    }
+    Ok(())
+  }
+
+  pub fn poll_intervals(&self) -> PollIntervals {
+    self.events.intervals()
+  }
+
+  // A second socket to the camera for background threads that make
+  // inquiries, so their replies don't reach the command queue
+  fn side_socket(&self) -> Result<UdpSocket> {
+    let addr = self.socket.peer_addr()
+      .map_err(ViscaError::CameraConnectionError)?;
+    let socket = UdpSocket::bind("0.0.0.0:0")
+      .map_err(ViscaError::CameraConnectionError)?;
+    socket.connect(addr)
+      .map_err(ViscaError::CameraConnectionError)?;
+    Ok(socket)
+  }
+
+  fn note_motion(&self, command: &impl ViscaCommand) {
+    if let Some(watchdog) = &self.watchdog {
+      if command.is_motion() {
+        watchdog.arm();
+      }
+    }
+  }
+
+  /// Sends `command` and blocks until the camera reports its completion.
+  ///
+  /// Unlike `set`, this waits for the Completion reply on the socket the camera
+  /// acknowledged the command on, so long operations such as
+  /// `PanTilt::AbsolutePos` or `Preset::Recall` can be chained. Returns
+  /// `ViscaError::CommandTimeoutError` if no completion arrives within `timeout`.
+  pub fn execute(&mut self, command: impl ViscaCommand, timeout: Duration) -> Result<()> {
+    let ticket = self.submit(command)?;
+    self.wait(ticket, timeout).map(|_| ())
+  }
+
+  /// Sends `inquiry` and returns the camera's reply payload, to be read with
+  /// `ViscaInquiry::parse_reply` or `parse_value`. Waits up to the timeout set
+  /// with `set_timout`, one second if none is set. With `enable_cache` and
+  /// `CacheConfig::answer_inquiries`, a fresh cached reply is returned instead,
+  /// except for positions and other values the camera changes by itself.
+  pub fn inquire(&mut self, inquiry: &impl ViscaInquiry) -> Result<Vec<u8>> {
+    self.check_restart();
+    let bytes = inquiry.bytes();
+    if let Some(reply) = self.cache.as_ref().and_then(|c| c.answer(&bytes)) {
+      return Ok(reply);
+    }
+    let pending = self.queue.pending(inquiry.msg_type(), bytes.clone());
+    let ticket = pending.ticket;
+    self.queue.push(pending);
+    self.dispatch_waiting()?;
+    let reply = self.wait(ticket, self.timeout.unwrap_or(DEFAULT_TIMEOUT))?;
+    if let Some(cache) = &mut self.cache {
+      cache.inquired(bytes, reply.clone());
+    }
+    Ok(reply)
+  }
+
+  /// Queues `command` for the next free command socket and returns a ticket
+  /// identifying it in the results of `poll`.
+  ///
+  /// Commands for which `ViscaCommand::bypasses_queue` is true (stops and
+  /// cancels) are sent straight away, ahead of anything waiting.
+  pub fn submit(&mut self, command: impl ViscaCommand) -> Result<Ticket> {
+    self.check_restart();
+    let bytes = self.fence(command.bytes())?;
+    self.note_motion(&command);
+    if let Some(cache) = &mut self.cache {
+      cache.sent(&bytes);
+    }
+    let pending = self.queue.pending(command.msg_type(), bytes);
+    let ticket = pending.ticket;
+    if command.bypasses_queue() {
+      self.dispatch(pending)?;
+    } else {
+      self.queue.push(pending);
+      self.dispatch_waiting()?;
+    }
+    Ok(ticket)
+  }
+
+  /// Reads every reply that has arrived, sends waiting commands to any sockets
+  /// that have freed up, and returns the commands that finished since the last
+  /// call. Never blocks.
+  pub fn poll(&mut self) -> Result<Vec<Finished>> {
+    while self.receive(Duration::ZERO)? {}
+    self.dispatch_waiting()?;
+    Ok(self.queue.drain_finished())
+  }
+
+  /// Number of commands held back waiting for a free socket
+  pub fn queue_depth(&self) -> usize {
+    self.queue.depth()
+  }
+
+  /// Number of commands sent to the camera that have not yet completed
+  pub fn in_flight(&self) -> usize {
+    self.queue.in_flight()
+  }
+
+  /// Drops every waiting command and cancels the ones executing on the camera.
+  /// Returns the tickets of the commands that were never sent.
+  pub fn cancel_all(&mut self) -> Result<Vec<Ticket>> {
+    let dropped = self.queue.clear();
+    for socket in self.queue.sockets().into_iter().filter_map(|s| params::Socket::new(s).ok()) {
+      self.submit(commands::Cancel(socket))?;
+    }
+    Ok(dropped)
+  }
+
+  fn dispatch(&mut self, pending: queue::Pending) -> Result<()> {
+    let seqnum = send_packet(&self.socket, &self.seqnum, pending.msg_type, &pending.bytes)
+      .map_err(ViscaError::CameraConnectionError)?;
+    self.queue.sent(pending, seqnum);
+    Ok(())
+  }
+
+  fn dispatch_waiting(&mut self) -> Result<()> {
+    while let Some(pending) = self.queue.next_ready() {
+      self.dispatch(pending)?;
+    }
+    Ok(())
+  }
+
+  fn wait(&mut self, ticket: Ticket, timeout: Duration) -> Result<Vec<u8>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+      if let Some(reply) = self.queue.take_finished(ticket) {
+        return reply;
+      }
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      if remaining.is_zero() {
+        self.queue.withdraw(ticket);
+        return Err(ViscaError::CommandTimeoutError);
+      }
+      self.receive(remaining)?;
+      self.dispatch_waiting()?;
+    }
+  }
+
+  // Waits up to `timeout` for a single packet and hands any reply to the queue.
+  // Returns false if nothing arrived in time.
+  fn receive(&mut self, timeout: Duration) -> Result<bool> {
+    self.check_restart();
+    let result = if timeout.is_zero() {
+      self.socket.set_nonblocking(true)
+        .map_err(ViscaError::CameraConnectionError)?;
+      let mut buf = [0u8; 1500];
+      let result = self.socket.recv(&mut buf).map(|received| buf[..received].to_vec());
+      self.socket.set_nonblocking(false)
+        .map_err(ViscaError::CameraConnectionError)?;
+      result
+    } else {
+      self.socket.set_read_timeout(Some(timeout))
+        .map_err(ViscaError::CameraConnectionError)?;
+      let mut buf = [0u8; 1500];
+      self.socket.recv(&mut buf).map(|received| buf[..received].to_vec())
+    };
+
+    let received = match result {
+      Ok(received) => received,
+      Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+        return Ok(false)
+      }
+      Err(e) => return Err(ViscaError::CameraConnectionError(e)),
+    };
+
+    if let Some(packet) = Packet::parse(&received) {
+      if packet.msg_type == MessageType::Reply {
+        if let Some(done) = self.queue.handle_reply(packet.seqnum, packet.payload) {
+          if let (Some(cache), queue::Done::Completed(_, command)) = (&mut self.cache, &done) {
+            cache.completed(command);
+          }
+          self.events.command_done(&done);
+        }
+      }
+    }
+    Ok(true)
+  }
+
+  pub fn send_bytes(&mut self, message_type: MessageType, bytes: &[u8]) -> io::Result<()> {
+    send_packet(&self.socket, &self.seqnum, message_type, bytes).map(|_| ())
+  }
 
   pub fn scan(timout: u64) -> io::Result<Vec<String>> {
     let sock = UdpSocket::bind("0.0.0.0:52380")?;
@@ -131,7 +499,7 @@ impl Camera {
             .filter(|byte| byte.is_ascii())
           {
             if let Ok(text) = String::from_utf8(elem.to_vec()){
-              camreply.push_str(&format!("{text}"));
+              camreply.push_str(&text);
             }
           }
           output.push(camreply);
@@ -141,6 +509,44 @@ impl Camera {
   }
 }
 
+impl Drop for Camera {
+  fn drop(&mut self) {
+    if self.watchdog.take().is_some() {
+      for bytes in watchdog::stop_bytes() {
+        let _ = self.send_bytes(MessageType::Command, &bytes);
+      }
+    }
+  }
+}
+
+// Frames `bytes` with the VISCA over IP header and sends it, returning the
+// sequence number used. Shared with the watchdog thread, which holds a clone of
+// the socket, so that both draw from the same sequence.
+fn send_packet(socket: &UdpSocket, seqnum: &AtomicU32, message_type: MessageType, bytes: &[u8]) -> io::Result<u32> {
+  let payload_type: [u8; 2] = (message_type as u16).to_be_bytes();
+
+  let payload_length = bytes.len() as u16;
+  let payload_length = payload_length.to_be_bytes();
+  let current = seqnum.fetch_add(1, Ordering::SeqCst);
+  let seq: [u8; 4] = current.to_be_bytes();
+
+  // TODO: simplify commands by using redundancies
+  // payload for all commands begins with 0x81, {0x01 or 0x04}
+  // payload for all inquiries begins with 0x09, {0x01 or 0x04}
+
+  let message = [
+    &payload_type,
+    &payload_length,
+    &seq[0..=1],
+    &seq[2..=3],
+    bytes,
+  ]
+  .concat();
+
+  socket.send(&message)?;
+  Ok(current)
+}
+
 fn vec_contains_slice<T: PartialEq>(v: &[T], s: &[T]) -> Option<usize> {
   if v.len() < s.len() {
     return None;
@@ -155,4 +561,47 @@ fn vec_contains_slice<T: PartialEq>(v: &[T], s: &[T]) -> Option<usize> {
     return Some(i);
   }
   None
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Stands in for the camera: the next packet the camera sent, and where from
+  fn next_packet(socket: &UdpSocket) -> (u32, Vec<u8>, std::net::SocketAddr) {
+    let mut buf = [0u8; 1500];
+    let (received, from) = socket.recv_from(&mut buf).unwrap();
+    let packet = Packet::parse(&buf[..received]).unwrap();
+    (packet.seqnum, packet.payload.to_vec(), from)
+  }
+
+  fn reply(socket: &UdpSocket, to: std::net::SocketAddr, seqnum: u32, payload: &[u8]) {
+    let length = (payload.len() as u16).to_be_bytes();
+    let header = [&MessageType::Reply.to_be_bytes()[..], &length, &seqnum.to_be_bytes()].concat();
+    socket.send_to(&[&header[..], payload].concat(), to).unwrap();
+  }
+
+  #[test]
+  fn cancel_all_cancels_acknowledged_sockets() {
+    let fake = UdpSocket::bind("127.0.0.1:0").unwrap();
+    fake.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let mut camera = Camera::new(&fake.local_addr().unwrap().to_string()).unwrap();
+
+    camera.submit(commands::Exposure::ModeManual).unwrap();
+    camera.submit(commands::Exposure::ModeIrisPri).unwrap();
+    let waiting = camera.submit(commands::Exposure::ModeFullAuto).unwrap();
+    for socket in [0x41, 0x42] {
+      let (seqnum, _, from) = next_packet(&fake);
+      reply(&fake, from, seqnum, &[0x90, socket, 0xFF]);
+    }
+    // Until both ACKs have been read
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while camera.queue.sockets().len() < 2 && Instant::now() < deadline {
+      camera.poll().unwrap();
+    }
+
+    assert_eq!(camera.cancel_all().unwrap(), [waiting]);
+    assert_eq!(camera.queue_depth(), 0);
+    assert_eq!(next_packet(&fake).1, [0x81, 0x21, 0xFF]);
+    assert_eq!(next_packet(&fake).1, [0x81, 0x22, 0xFF]);
+  }
+}
@@ -0,0 +1,465 @@
+// Host side soft limits and no-go zones.
+//
+// `PanTilt::LimitSet` gives the camera a single rectangle. A `Geofence` adds
+// pan/tilt ranges and any number of polygon zones the camera must not point
+// into, each optionally only while zoomed in past a given position (a crowd
+// may be fine in a wide shot but not in a close up). Angles are in degrees as
+// used by `motion::pan_degrees`.
+//
+// With a geofence set, `Camera::set` and `Camera::submit` check every pan/tilt
+// and zoom command against the tracked position, read by inquiry whenever it
+// isn't known. An absolute or relative target in a zone is rejected, or with
+// `Enforcement::Clamp` moved to the nearest allowed point, and a continuous
+// move or zoom heading into a zone is rejected or turned into a stop. Only the
+// target of a positioning move is checked, not the path the camera takes.
+// `PanTilt::Home` is checked as an absolute move to pan and tilt 0. A memory
+// recall (`Preset::Recall`) goes to a position the host doesn't know, so it is
+// always rejected while a geofence is set. Stops are always sent as they are.
+// While a continuous move runs, `Camera::check_geofence` stops it before it
+// crosses into a zone; `drive::Drive` calls it on every update.
+
+use std::io;
+use std::time::Duration;
+
+use crate::commands::{PanTilt, Zoom};
+use crate::messages::ViscaMessage;
+use crate::motion::{self, Position, SpeedTable};
+use crate::params::{PanTiltSpeed, VarSpeed};
+use crate::{messages, Camera, MessageType, Result, ViscaError};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zone {
+    pub polygon: Vec<(f32, f32)>, // pan, tilt corners in degrees
+    pub min_zoom: u16,            // forbidden from this zoom position up, 0 = always
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Enforcement {
+    Reject, // fail the command with `ViscaError::Geofenced`
+    #[default]
+    Clamp, // send the nearest allowed target, or a stop
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geofence {
+    pub pan: Option<(f32, f32)>, // allowed range, degrees
+    pub tilt: Option<(f32, f32)>,
+    pub zones: Vec<Zone>,
+    pub enforcement: Enforcement,
+    pub lookahead: Duration, // how far ahead continuous moves are checked
+    pub speeds: SpeedTable,  // to judge how far a continuous move gets in `lookahead`
+}
+
+impl Default for Geofence {
+    fn default() -> Self {
+        Geofence {
+            pan: None,
+            tilt: None,
+            zones: Vec::new(),
+            enforcement: Enforcement::default(),
+            lookahead: Duration::from_millis(300),
+            speeds: SpeedTable::default(),
+        }
+    }
+}
+
+impl Zone {
+    // Ray casting; points on the edge count as outside
+    pub fn contains(&self, pan: f32, tilt: f32) -> bool {
+        let mut inside = false;
+        let n = self.polygon.len();
+        for i in 0..n {
+            let (x1, y1) = self.polygon[i];
+            let (x2, y2) = self.polygon[(i + 1) % n];
+            if (y1 > tilt) != (y2 > tilt) && pan < x1 + (tilt - y1) / (y2 - y1) * (x2 - x1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    // Closest point on the outline to (pan, tilt)
+    fn nearest_edge(&self, pan: f32, tilt: f32) -> Option<(f32, f32)> {
+        let n = self.polygon.len();
+        (0..n)
+            .map(|i| {
+                let (x1, y1) = self.polygon[i];
+                let (x2, y2) = self.polygon[(i + 1) % n];
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 { (((pan - x1) * dx + (tilt - y1) * dy) / length).clamp(0.0, 1.0) } else { 0.0 };
+                (x1 + t * dx, y1 + t * dy)
+            })
+            .min_by(|a, b| distance(*a, (pan, tilt)).total_cmp(&distance(*b, (pan, tilt))))
+    }
+}
+
+fn distance((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> f32 {
+    ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
+}
+
+impl Geofence {
+    /// Whether the camera may point at (pan, tilt) with the zoom at `zoom`
+    pub fn allows(&self, pan: f32, tilt: f32, zoom: u16) -> bool {
+        let within = |range: Option<(f32, f32)>, v: f32| range.is_none_or(|(min, max)| (min..=max).contains(&v));
+        within(self.pan, pan)
+            && within(self.tilt, tilt)
+            && !self.zones.iter().any(|z| zoom >= z.min_zoom && z.contains(pan, tilt))
+    }
+
+    /// The allowed point nearest to (pan, tilt), if one can be found
+    pub fn clamp(&self, pan: f32, tilt: f32, zoom: u16) -> Option<(f32, f32)> {
+        let clamp = |range: Option<(f32, f32)>, v: f32| range.map_or(v, |(min, max)| v.clamp(min, max));
+        let (mut pan, mut tilt) = (clamp(self.pan, pan), clamp(self.tilt, tilt));
+        for zone in &self.zones {
+            if zoom < zone.min_zoom || !zone.contains(pan, tilt) {
+                continue;
+            }
+            let (x, y) = zone.nearest_edge(pan, tilt)?;
+            // Just past the edge, so the point is clearly outside
+            let d = distance((pan, tilt), (x, y)).max(f32::EPSILON);
+            pan = x + (x - pan) / d * 0.01;
+            tilt = y + (y - tilt) / d * 0.01;
+        }
+        self.allows(pan, tilt, zoom).then_some((pan, tilt))
+    }
+
+    /// The highest zoom position allowed at (pan, tilt)
+    pub fn max_zoom(&self, pan: f32, tilt: f32) -> Option<u16> {
+        self.zones
+            .iter()
+            .filter(|z| z.contains(pan, tilt))
+            .map(|z| z.min_zoom.saturating_sub(1))
+            .min()
+    }
+
+    // Whether a move from (pan, tilt) at the given rates (degrees per second,
+    // signed) enters a forbidden area within `lookahead`
+    fn heading_in(&self, (pan, tilt): (f32, f32), (pan_rate, tilt_rate): (f32, f32), zoom: u16) -> bool {
+        let seconds = self.lookahead.as_secs_f32();
+        (1..=8).any(|step| {
+            let t = seconds * step as f32 / 8.0;
+            !self.allows(pan + pan_rate * t, tilt + tilt_rate * t, zoom)
+        })
+    }
+}
+
+// Pan/tilt and zoom commands decoded from their bytes, so any command type
+// can be checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Move {
+    Absolute(PanTiltSpeed, u32, u16),
+    Relative(PanTiltSpeed, u32, u16),
+    Drive(i8, i8, PanTiltSpeed, PanTiltSpeed), // direction of pan (+ left) and tilt (+ up)
+    ZoomDirect(u16),
+    ZoomDrive(i8, VarSpeed), // + tele, - wide, 0 stop
+    Recall,                  // camera memory, position unknown
+}
+
+fn decode(bytes: &[u8]) -> Option<Move> {
+    let nibbles = |b: &[u8]| b.iter().fold(0u32, |acc, &n| (acc << 4) | (n & 0x0F) as u32);
+    let direction = |d: u8| match d {
+        0x01 => 1,
+        0x02 => -1,
+        _ => 0,
+    };
+    match bytes {
+        [_, 0x01, 0x06, 0x01, vv, ww, d1, d2, 0xFF] => Some(Move::Drive(
+            direction(*d1),
+            direction(*d2),
+            PanTiltSpeed::clamped(*vv),
+            PanTiltSpeed::clamped(*ww),
+        )),
+        [_, 0x01, 0x06, kind @ (0x02 | 0x03), vv, _, p @ .., 0xFF] if p.len() == 9 => {
+            let (speed, pan, tilt) = (PanTiltSpeed::clamped(*vv), nibbles(&p[..5]), nibbles(&p[5..]) as u16);
+            Some(if *kind == 0x02 { Move::Absolute(speed, pan, tilt) } else { Move::Relative(speed, pan, tilt) })
+        }
+        [_, 0x01, 0x06, 0x04, 0xFF] => Some(Move::Absolute(PanTiltSpeed::MAX, 0, 0)),
+        [_, 0x01, 0x04, 0x3F, 0x02, _, 0xFF] => Some(Move::Recall),
+        [_, 0x01, 0x04, 0x47, z @ .., 0xFF] if z.len() == 4 => Some(Move::ZoomDirect(nibbles(z) as u16)),
+        [_, 0x01, 0x04, 0x07, x, 0xFF] => {
+            let speed = VarSpeed::clamped(messages::u8bot(*x));
+            match x {
+                0x02 => Some(Move::ZoomDrive(1, VarSpeed::MIN)),
+                0x03 => Some(Move::ZoomDrive(-1, VarSpeed::MIN)),
+                0x20..=0x27 => Some(Move::ZoomDrive(1, speed)),
+                0x30..=0x37 => Some(Move::ZoomDrive(-1, speed)),
+                _ => Some(Move::ZoomDrive(0, speed)),
+            }
+        }
+        _ => None,
+    }
+}
+
+// A geofence with what is known of where the camera is and what it's doing
+pub(crate) struct Fence {
+    geofence: Geofence,
+    tracked: Option<Position>,
+    pan_tilt: (f32, f32), // rates of a continuous move in progress, degrees per second
+    zoom: f32,            // rate of a continuous zoom in progress, positions per second
+}
+
+impl Camera {
+    pub fn set_geofence(&mut self, geofence: Option<Geofence>) {
+        self.fence = geofence.map(|geofence| Fence { geofence, tracked: None, pan_tilt: (0.0, 0.0), zoom: 0.0 });
+    }
+
+    pub fn geofence(&self) -> Option<&Geofence> {
+        self.fence.as_ref().map(|f| &f.geofence)
+    }
+
+    /// With a continuous move or zoom in progress, reads the position and
+    /// stops whatever would enter a forbidden area within the geofence's
+    /// lookahead. Returns true if anything was stopped.
+    pub fn check_geofence(&mut self) -> Result<bool> {
+        let moving = self.fence.as_ref().is_some_and(|f| f.pan_tilt != (0.0, 0.0) || f.zoom != 0.0);
+        if !moving {
+            return Ok(false);
+        }
+        let position = self.fenced_position()?;
+        let Some(fence) = &mut self.fence else { return Ok(false) };
+        let here = (motion::pan_degrees(position.pan), motion::tilt_degrees(position.tilt));
+        let zoom_ahead = position.zoom as f32 + fence.zoom * fence.geofence.lookahead.as_secs_f32();
+        let zoom_ahead = zoom_ahead.clamp(0.0, u16::MAX as f32) as u16;
+
+        let mut stops = Vec::new();
+        if fence.pan_tilt != (0.0, 0.0) && fence.geofence.heading_in(here, fence.pan_tilt, position.zoom) {
+            fence.pan_tilt = (0.0, 0.0);
+            stops.push(PanTilt::Stop(PanTiltSpeed::MIN, PanTiltSpeed::MIN).bytes());
+        }
+        if fence.zoom > 0.0 && !fence.geofence.allows(here.0, here.1, zoom_ahead) {
+            fence.zoom = 0.0;
+            stops.push(Zoom::Stop.bytes());
+        }
+        for bytes in &stops {
+            self.send_bytes(MessageType::Command, bytes).map_err(ViscaError::CameraConnectionError)?;
+        }
+        Ok(!stops.is_empty())
+    }
+
+    // Forgets the tracked position and any motion in progress, e.g. after
+    // the camera restarted
+    pub(crate) fn forget_fenced_position(&mut self) {
+        if let Some(f) = &mut self.fence {
+            f.tracked = None;
+            f.pan_tilt = (0.0, 0.0);
+            f.zoom = 0.0;
+        }
+    }
+
+    // The position to check commands against. Read from the camera if it's
+    // not known or something is moving continuously.
+    fn fenced_position(&mut self) -> Result<Position> {
+        match &self.fence {
+            Some(f) if f.pan_tilt == (0.0, 0.0) && f.zoom == 0.0 && f.tracked.is_some() => Ok(f.tracked.unwrap_or_default()),
+            _ => {
+                let position = self.position()?;
+                if let Some(f) = &mut self.fence {
+                    f.tracked = Some(position);
+                }
+                Ok(position)
+            }
+        }
+    }
+
+    // Checks a command about to be sent against the geofence, returning the
+    // bytes to send instead: the same, a clamped target or a stop
+    pub(crate) fn fence(&mut self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let Some(m) = self.fence.as_ref().and(decode(&bytes)) else { return Ok(bytes) };
+        // A stop goes out as it is, without waiting on a position read that
+        // could fail and keep the camera moving
+        match (m, &mut self.fence) {
+            (Move::Drive(0, 0, ..), Some(fence)) => {
+                fence.pan_tilt = (0.0, 0.0);
+                return Ok(bytes);
+            }
+            (Move::ZoomDrive(0, _), Some(fence)) => {
+                fence.zoom = 0.0;
+                return Ok(bytes);
+            }
+            (Move::Recall, _) => return Err(ViscaError::Geofenced),
+            _ => {}
+        }
+        let position = self.fenced_position()?;
+        let Some(fence) = &mut self.fence else { return Ok(bytes) };
+        let geofence = &fence.geofence;
+        let clamp = geofence.enforcement == Enforcement::Clamp;
+        let here = (motion::pan_degrees(position.pan), motion::tilt_degrees(position.tilt));
+
+        let target = |speed, pan: f32, tilt: f32| -> Result<(Vec<u8>, u32, u16)> {
+            if geofence.allows(pan, tilt, position.zoom) {
+                let (p, t) = (motion::pan_position(pan), motion::tilt_position(tilt));
+                return Ok((PanTilt::AbsolutePos(speed, p, t).bytes(), p, t));
+            }
+            match geofence.clamp(pan, tilt, position.zoom).filter(|_| clamp) {
+                Some((pan, tilt)) => {
+                    let (p, t) = (motion::pan_position(pan), motion::tilt_position(tilt));
+                    Ok((PanTilt::AbsolutePos(speed, p, t).bytes(), p, t))
+                }
+                None => Err(ViscaError::Geofenced),
+            }
+        };
+
+        match m {
+            Move::Absolute(speed, p, t) | Move::Relative(speed, p, t) => {
+                let (mut pan, mut tilt) = (motion::pan_degrees(p), motion::tilt_degrees(t));
+                if matches!(m, Move::Relative(..)) {
+                    pan += here.0;
+                    tilt += here.1;
+                }
+                // Relative moves are sent as the equivalent absolute move, so
+                // the tracked position stays exact
+                let (out, p, t) = target(speed, pan, tilt)?;
+                fence.pan_tilt = (0.0, 0.0);
+                fence.tracked = Some(Position { pan: p, tilt: t, ..position });
+                Ok(out)
+            }
+            Move::Drive(pan, tilt, vv, ww) => {
+                let rates = (pan as f32 * geofence.speeds.pan_rate(vv), tilt as f32 * geofence.speeds.tilt_rate(ww));
+                if rates != (0.0, 0.0) && geofence.heading_in(here, rates, position.zoom) {
+                    if !clamp {
+                        return Err(ViscaError::Geofenced);
+                    }
+                    fence.pan_tilt = (0.0, 0.0);
+                    return Ok(PanTilt::Stop(vv, ww).bytes());
+                }
+                fence.pan_tilt = rates;
+                Ok(bytes)
+            }
+            Move::ZoomDirect(zoom) => {
+                let limit = geofence.max_zoom(here.0, here.1).unwrap_or(u16::MAX);
+                let zoom = match zoom > limit {
+                    true if clamp => limit,
+                    true => return Err(ViscaError::Geofenced),
+                    false => zoom,
+                };
+                fence.zoom = 0.0;
+                fence.tracked = Some(Position { zoom, ..position });
+                Ok(Zoom::Direct(zoom).bytes())
+            }
+            Move::ZoomDrive(direction, speed) => {
+                let limit = geofence.max_zoom(here.0, here.1).unwrap_or(u16::MAX);
+                let rate = direction as f32 * geofence.speeds.zoom_rate(speed);
+                let ahead = position.zoom as f32 + rate * geofence.lookahead.as_secs_f32();
+                if direction > 0 && ahead > limit as f32 {
+                    if !clamp {
+                        return Err(ViscaError::Geofenced);
+                    }
+                    fence.zoom = 0.0;
+                    return Ok(Zoom::Stop.bytes());
+                }
+                fence.zoom = rate;
+                Ok(bytes)
+            }
+            Move::Recall => Err(ViscaError::Geofenced),
+        }
+    }
+}
+
+// `Camera::set` reports errors as `io::Error`; a geofence rejection is passed
+// through as `ErrorKind::PermissionDenied` wrapping `ViscaError::Geofenced`
+pub(crate) fn into_io(e: ViscaError) -> io::Error {
+    match e {
+        ViscaError::CameraConnectionError(e) => e,
+        e => io::Error::new(io::ErrorKind::PermissionDenied, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::Preset;
+    use crate::params::PresetNumber;
+
+    fn square(min_zoom: u16) -> Zone {
+        Zone { polygon: vec![(10.0, -5.0), (20.0, -5.0), (20.0, 5.0), (10.0, 5.0)], min_zoom }
+    }
+
+    #[test]
+    fn zone_contains() {
+        let zone = square(0);
+        assert!(zone.contains(15.0, 0.0));
+        assert!(!zone.contains(5.0, 0.0));
+        assert!(!zone.contains(15.0, 6.0));
+
+        let triangle = Zone { polygon: vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)], min_zoom: 0 };
+        assert!(triangle.contains(2.0, 2.0));
+        assert!(!triangle.contains(6.0, 6.0));
+        assert!(!Zone { polygon: Vec::new(), min_zoom: 0 }.contains(0.0, 0.0));
+    }
+
+    #[test]
+    fn clamp_to_ranges() {
+        let fence = Geofence { pan: Some((-90.0, 90.0)), tilt: Some((-20.0, 30.0)), ..Geofence::default() };
+        assert_eq!(fence.clamp(0.0, 0.0, 0), Some((0.0, 0.0)));
+        assert_eq!(fence.clamp(120.0, -45.0, 0), Some((90.0, -20.0)));
+    }
+
+    #[test]
+    fn clamp_out_of_zone() {
+        let fence = Geofence { zones: vec![square(0x1000)], ..Geofence::default() };
+        // Wide enough to be allowed
+        assert_eq!(fence.clamp(12.0, 1.0, 0), Some((12.0, 1.0)));
+
+        let (pan, tilt) = fence.clamp(12.0, 1.0, 0x2000).unwrap();
+        assert!(pan < 10.0 && (pan - 10.0).abs() < 0.1, "pan {pan}");
+        assert_eq!(tilt, 1.0);
+        assert!(fence.allows(pan, tilt, 0x2000));
+        assert_eq!(fence.max_zoom(12.0, 1.0), Some(0x0FFF));
+    }
+
+    // A camera that never needs to be asked where it is
+    fn fenced(enforcement: Enforcement) -> Camera {
+        let mut camera = Camera::new("127.0.0.1:52381").unwrap();
+        let zone = Zone { polygon: vec![(-5.0, -5.0), (5.0, -5.0), (5.0, 5.0), (-5.0, 5.0)], min_zoom: 0 };
+        camera.set_geofence(Some(Geofence { zones: vec![zone], enforcement, ..Geofence::default() }));
+        let tracked = Position { pan: motion::pan_position(20.0), tilt: 0, zoom: 0 };
+        camera.fence.as_mut().unwrap().tracked = Some(tracked);
+        camera
+    }
+
+    #[test]
+    fn home_is_checked() {
+        assert_eq!(decode(&PanTilt::Home.bytes()), Some(Move::Absolute(PanTiltSpeed::MAX, 0, 0)));
+
+        let mut camera = fenced(Enforcement::Reject);
+        assert!(matches!(camera.fence(PanTilt::Home.bytes()), Err(ViscaError::Geofenced)));
+
+        let mut camera = fenced(Enforcement::Clamp);
+        let Some(Move::Absolute(_, pan, tilt)) = decode(&camera.fence(PanTilt::Home.bytes()).unwrap()) else {
+            panic!("home not sent as an absolute move");
+        };
+        let (pan, tilt) = (motion::pan_degrees(pan), motion::tilt_degrees(tilt));
+        assert!(camera.geofence().unwrap().allows(pan, tilt, 0), "sent {pan}, {tilt}");
+    }
+
+    #[test]
+    fn recall_is_rejected() {
+        let recall = Preset::Recall(PresetNumber::new(3).unwrap()).bytes();
+        assert_eq!(decode(&recall), Some(Move::Recall));
+        for enforcement in [Enforcement::Reject, Enforcement::Clamp] {
+            let mut camera = fenced(enforcement);
+            // Without reading the position first
+            camera.fence.as_mut().unwrap().tracked = None;
+            assert!(matches!(camera.fence(recall.clone()), Err(ViscaError::Geofenced)));
+        }
+
+        let mut camera = Camera::new("127.0.0.1:52381").unwrap();
+        assert_eq!(camera.fence(recall.clone()).unwrap(), recall);
+    }
+
+    #[test]
+    fn clamp_fails_when_nothing_allowed() {
+        // The zone covers the whole allowed range
+        let fence = Geofence {
+            pan: Some((10.0, 20.0)),
+            tilt: Some((-5.0, 5.0)),
+            zones: vec![square(0)],
+            ..Geofence::default()
+        };
+        assert_eq!(fence.clamp(15.0, 0.0, 0), None);
+    }
+}
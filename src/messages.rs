@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum MessageType {
     Command = 0x0100u16,
@@ -17,6 +18,71 @@ impl MessageType {
     }
 }
 
+impl MessageType {
+    pub fn from_u16(val: u16) -> Option<Self> {
+        match val {
+            0x0100 => Some(MessageType::Command),
+            0x0110 => Some(MessageType::Inquiry),
+            0x0111 => Some(MessageType::Reply),
+            0x0120 => Some(MessageType::DevSettingCommand),
+            0x0200 => Some(MessageType::ControlCommand),
+            0x0201 => Some(MessageType::ControlReply),
+            _ => None,
+        }
+    }
+}
+
+// A VISCA over IP packet received from the camera
+#[derive(Debug, PartialEq)]
+pub struct Packet<'a> {
+    pub msg_type: MessageType,
+    pub seqnum: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Packet<'a> {
+    // Header layout: payload type (2), payload length (2), sequence number (4)
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let header = bytes.get(..8)?;
+        let msg_type = MessageType::from_u16(u16::from_be_bytes([header[0], header[1]]))?;
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let seqnum = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let payload = bytes.get(8..8 + length)?;
+        Some(Packet { msg_type, seqnum, payload })
+    }
+}
+
+// The three kinds of reply the camera sends to a command or inquiry
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Ack(u8),                 // 90 4y FF (y: socket number)
+    Completion(u8, Vec<u8>), // 90 5y ... FF (inquiry data, if any)
+    Error(u8, u8),           // 90 6y ee FF (ee: error code)
+}
+
+impl Reply {
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        match payload {
+            [0x90, kind, rest @ .., 0xFF] => {
+                let socket = u8bot(*kind);
+                match u8top(*kind) {
+                    0x4 => Some(Reply::Ack(socket)),
+                    0x5 => Some(Reply::Completion(socket, rest.to_vec())),
+                    0x6 => rest.first().map(|code| Reply::Error(socket, *code)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// Cancel (8x 2p FF) gets no reply of its own; the camera answers on the
+// socket of the command being cancelled instead
+pub fn expects_reply(bytes: &[u8]) -> bool {
+    !matches!(bytes, [_, p, 0xFF] if u8top(*p) == 0x2)
+}
+
 pub trait ViscaMessage {
     fn bytes(&self) -> Vec<u8>;
     fn msg_type(&self) -> MessageType;
@@ -24,10 +90,32 @@ pub trait ViscaMessage {
 
 pub trait ViscaInquiry : ViscaMessage {
     fn parse_reply(&self, bytes: &[u8]) -> String;
+
+    // The reply's parameter as a number, e.g. 0x4000 for a zoom position.
+    // None for replies that don't carry a single value.
+    fn parse_value(&self, _bytes: &[u8]) -> Option<u32> {
+        None
+    }
 }
 
 pub trait ViscaCommand : ViscaMessage {
     fn parse_reply(&self, bytes: &[u8]) -> String;
+
+    // Commands that must reach the camera immediately (stops, cancels)
+    // rather than waiting behind queued commands for a free socket
+    fn bypasses_queue(&self) -> bool {
+        false
+    }
+
+    // Continuous movements that keep going until a stop is sent
+    fn is_motion(&self) -> bool {
+        false
+    }
+
+    // Pan/tilt, zoom and focus commands, i.e. those that change the shot
+    fn is_ptzf(&self) -> bool {
+        false
+    }
 }
 
 // Util functions to extract nibbles from u8/16/32
@@ -92,4 +180,37 @@ pub fn merge_u16(top: u8, midtop: u8, midbot: u8, bot: u8) -> u16 {
     let midbot = midbot as u16;
     let bot = bot as u16;
     (top << 12) | (midtop << 8) | (midbot << 4) | bot
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_header() {
+        let bytes = [0x01, 0x11, 0x00, 0x03, 0x00, 0x00, 0x01, 0x02, 0x90, 0x41, 0xFF];
+        let packet = Packet::parse(&bytes).unwrap();
+        assert_eq!(packet, Packet { msg_type: MessageType::Reply, seqnum: 0x0102, payload: &[0x90, 0x41, 0xFF] });
+
+        // Shorter than the length it claims, or an unknown payload type
+        assert_eq!(Packet::parse(&bytes[..10]), None);
+        assert_eq!(Packet::parse(&[0x09, 0x99, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn replies() {
+        assert_eq!(Reply::parse(&[0x90, 0x41, 0xFF]), Some(Reply::Ack(1)));
+        assert_eq!(Reply::parse(&[0x90, 0x52, 0xFF]), Some(Reply::Completion(2, Vec::new())));
+        assert_eq!(Reply::parse(&[0x90, 0x50, 0x02, 0xFF]), Some(Reply::Completion(0, vec![0x02])));
+        assert_eq!(Reply::parse(&[0x90, 0x61, 0x41, 0xFF]), Some(Reply::Error(1, 0x41)));
+        assert_eq!(Reply::parse(&[0x90, 0x60, 0x03, 0xFF]), Some(Reply::Error(0, 0x03)));
+        assert_eq!(Reply::parse(&[0x90, 0x60, 0xFF]), None);
+        assert_eq!(Reply::parse(&[0x90, 0x71, 0xFF]), None);
+        assert_eq!(Reply::parse(&[0x81, 0x41, 0xFF]), None);
+    }
+
+    #[test]
+    fn cancel_expects_no_reply() {
+        assert!(!expects_reply(&[0x81, 0x21, 0xFF]));
+        assert!(expects_reply(&[0x81, 0x01, 0x04, 0x07, 0x00, 0xFF]));
+    }
+}
@@ -0,0 +1,454 @@
+// Pan/tilt angles, axis speeds and planned moves.
+//
+// Pan positions are 20 bit and tilt positions 16 bit two's complement values
+// with 0 at centre, positive pan to the left and positive tilt up (see
+// `commands::PanAngle` and `commands::TiltAngle`).
+//
+// `PanTilt::AbsolutePos` takes a single speed and `Zoom::Direct` none at all,
+// so a move made from those arrives axis by axis. A `MovePlan` instead drives
+// each axis at its own variable speed, chosen from a `SpeedTable` so that pan,
+// tilt and zoom all take the requested time. Each axis is stopped as it
+// reaches its target, tracked with position inquiries, and the move is
+// finished with `AbsolutePos` and `Zoom::Direct` to land exactly.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::{Command, PanTilt, Zoom};
+use crate::inquiry;
+use crate::params::{PanTiltSpeed, VarSpeed};
+use crate::state::MOVE_TIMEOUT;
+use crate::{Camera, Result, ViscaError, ViscaInquiry};
+
+// From the reference points in `PanAngle`, 0x00937 per 10 degrees
+const UNITS_PER_DEGREE: f32 = 235.9;
+
+// How often positions are read while a planned move runs
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Time allowed for an axis to get up to speed before it is measured
+const RAMP: Duration = Duration::from_millis(300);
+
+// Most an axis is moved measuring one speed, kept well inside its range
+const MAX_PAN_TRAVEL: f32 = 90.0;
+const MAX_TILT_TRAVEL: f32 = 35.0;
+const MAX_ZOOM_TRAVEL: f32 = 12000.0;
+
+pub fn pan_degrees(position: u32) -> f32 {
+    pan_signed(position) as f32 / UNITS_PER_DEGREE
+}
+
+pub fn pan_position(degrees: f32) -> u32 {
+    ((degrees * UNITS_PER_DEGREE).round() as i32 as u32) & 0xFFFFF
+}
+
+pub fn tilt_degrees(position: u16) -> f32 {
+    position as i16 as f32 / UNITS_PER_DEGREE
+}
+
+pub fn tilt_position(degrees: f32) -> u16 {
+    (degrees * UNITS_PER_DEGREE).round() as i16 as u16
+}
+
+pub(crate) fn pan_signed(position: u32) -> i32 {
+    ((position << 12) as i32) >> 12
+}
+
+// Speed of each axis at each of its speed settings. `Camera::measure_speeds`
+// builds one for the camera at hand, saved as `axis = rate rate ...` lines.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpeedTable {
+    pub pan: [f32; 24],  // degrees per second at PanTiltSpeed 01 - 18
+    pub tilt: [f32; 24], // degrees per second at PanTiltSpeed 01 - 18
+    pub zoom: [f32; 8],  // zoom positions per second at VarSpeed 0 - 7
+}
+
+// Nominal figures, a table measured on the actual camera is more accurate
+const NOMINAL_PAN_TILT: [f32; 24] = [
+    0.5, 0.62, 0.76, 0.93, 1.15, 1.42, 1.74, 2.15, 2.64, 3.26, 4.01, 4.94, 6.08, 7.48, 9.22, 11.35,
+    13.98, 17.21, 21.19, 26.09, 32.13, 39.57, 48.73, 60.0,
+];
+const NOMINAL_ZOOM: [f32; 8] = [546.0, 830.0, 1262.0, 1918.0, 2916.0, 4432.0, 6737.0, 10240.0];
+
+impl Default for SpeedTable {
+    fn default() -> Self {
+        SpeedTable {
+            pan: NOMINAL_PAN_TILT,
+            tilt: NOMINAL_PAN_TILT,
+            zoom: NOMINAL_ZOOM,
+        }
+    }
+}
+
+impl SpeedTable {
+    pub fn pan_rate(&self, speed: PanTiltSpeed) -> f32 {
+        self.pan[(speed.value() - PanTiltSpeed::MIN.value()) as usize]
+    }
+
+    pub fn tilt_rate(&self, speed: PanTiltSpeed) -> f32 {
+        self.tilt[(speed.value() - PanTiltSpeed::MIN.value()) as usize]
+    }
+
+    pub fn zoom_rate(&self, speed: VarSpeed) -> f32 {
+        self.zoom[speed.value() as usize]
+    }
+
+    /// The speed setting closest to `rate` degrees per second
+    pub fn pan_speed(&self, rate: f32) -> PanTiltSpeed {
+        PanTiltSpeed::clamped(nearest(&self.pan, rate) as u8 + PanTiltSpeed::MIN.value())
+    }
+
+    pub fn tilt_speed(&self, rate: f32) -> PanTiltSpeed {
+        PanTiltSpeed::clamped(nearest(&self.tilt, rate) as u8 + PanTiltSpeed::MIN.value())
+    }
+
+    /// The speed setting closest to `rate` zoom positions per second
+    pub fn zoom_speed(&self, rate: f32) -> VarSpeed {
+        VarSpeed::clamped(nearest(&self.zoom, rate) as u8)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for SpeedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (axis, rates) in [("pan", &self.pan[..]), ("tilt", &self.tilt[..]), ("zoom", &self.zoom[..])] {
+            write!(f, "{} =", axis)?;
+            for rate in rates {
+                write!(f, " {}", rate)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SpeedTable {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut table = SpeedTable::default();
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (key, values) = line.split_once('=').ok_or(ViscaError::ParameterOutOfRange("speed table line"))?;
+            let rates = values
+                .split_whitespace()
+                .map(|v| v.parse::<f32>().ok().filter(|r| *r > 0.0))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(ViscaError::ParameterOutOfRange("speed table rate"))?;
+            let axis: &mut [f32] = match key.trim() {
+                "pan" => &mut table.pan,
+                "tilt" => &mut table.tilt,
+                "zoom" => &mut table.zoom,
+                _ => return Err(ViscaError::ParameterOutOfRange("speed table axis")),
+            };
+            if rates.len() != axis.len() {
+                return Err(ViscaError::ParameterOutOfRange("speed table rates"));
+            }
+            axis.copy_from_slice(&rates);
+        }
+        Ok(table)
+    }
+}
+
+// Index of the closest rate, by ratio so slow speeds are matched as well as fast
+fn nearest(rates: &[f32], rate: f32) -> usize {
+    let error = |r: f32| (r / rate).ln().abs();
+    (0..rates.len())
+        .min_by(|&a, &b| error(rates[a]).total_cmp(&error(rates[b])))
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub pan: u32,
+    pub tilt: u16,
+    pub zoom: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovePlan {
+    pub from: Position,
+    pub to: Position,
+    pub pan_speed: Option<PanTiltSpeed>, // None for an axis that doesn't move
+    pub tilt_speed: Option<PanTiltSpeed>,
+    pub zoom_speed: Option<VarSpeed>,
+    pub duration: Duration, // expected, as near the requested time as the speed steps allow
+}
+
+impl MovePlan {
+    pub fn new(table: &SpeedTable, from: Position, to: Position, duration: Duration) -> MovePlan {
+        let seconds = duration.as_secs_f32().max(f32::EPSILON);
+        let pan = (pan_degrees(to.pan) - pan_degrees(from.pan)).abs();
+        let tilt = (tilt_degrees(to.tilt) - tilt_degrees(from.tilt)).abs();
+        let zoom = (to.zoom as f32 - from.zoom as f32).abs();
+
+        let pan_speed = (pan > 0.0).then(|| table.pan_speed(pan / seconds));
+        let tilt_speed = (tilt > 0.0).then(|| table.tilt_speed(tilt / seconds));
+        let zoom_speed = (zoom > 0.0).then(|| table.zoom_speed(zoom / seconds));
+
+        let expected = [
+            pan_speed.map_or(0.0, |s| pan / table.pan_rate(s)),
+            tilt_speed.map_or(0.0, |s| tilt / table.tilt_rate(s)),
+            zoom_speed.map_or(0.0, |s| zoom / table.zoom_rate(s)),
+        ];
+        MovePlan {
+            from,
+            to,
+            pan_speed,
+            tilt_speed,
+            zoom_speed,
+            duration: Duration::from_secs_f32(expected.into_iter().fold(0.0, f32::max)),
+        }
+    }
+
+    // Continuous pan/tilt move with the axes that are still running
+    fn pan_tilt_command(&self, pan: bool, tilt: bool) -> PanTilt {
+        let vv = self.pan_speed.unwrap_or(PanTiltSpeed::MIN);
+        let ww = self.tilt_speed.unwrap_or(PanTiltSpeed::MIN);
+        let left = pan_signed(self.to.pan) > pan_signed(self.from.pan);
+        let up = (self.to.tilt as i16) > (self.from.tilt as i16);
+        match (pan, tilt, left, up) {
+            (false, false, _, _) => PanTilt::Stop(vv, ww),
+            (false, true, _, true) => PanTilt::Up(vv, ww),
+            (false, true, _, false) => PanTilt::Down(vv, ww),
+            (true, false, true, _) => PanTilt::Left(vv, ww),
+            (true, false, false, _) => PanTilt::Right(vv, ww),
+            (true, true, true, true) => PanTilt::UpLeft(vv, ww),
+            (true, true, false, true) => PanTilt::UpRight(vv, ww),
+            (true, true, true, false) => PanTilt::DownLeft(vv, ww),
+            (true, true, false, false) => PanTilt::DownRight(vv, ww),
+        }
+    }
+
+    fn zoom_command(&self) -> Zoom {
+        let speed = self.zoom_speed.unwrap_or(VarSpeed::MIN);
+        if self.to.zoom > self.from.zoom { Zoom::TeleVar(speed) } else { Zoom::WideVar(speed) }
+    }
+}
+
+// Whether an axis moving from `from` to `to` at `rate` units per second will
+// get there before the next position reading
+fn arriving(from: i32, to: i32, now: i32, rate: f32) -> bool {
+    let remaining = if to > from { to - now } else { now - to };
+    remaining as f32 <= rate * POLL_INTERVAL.as_secs_f32()
+}
+
+impl Camera {
+    /// Reads the current pan/tilt and zoom positions
+    pub fn position(&mut self) -> Result<Position> {
+        let reply = self.inquire(&inquiry::PanTilt::Position)?;
+        let (pan, tilt) = inquiry::PanTilt::parse_position(&reply).ok_or(ViscaError::UnexpectedReply(reply))?;
+        let reply = self.inquire(&inquiry::Zoom::Position)?;
+        let zoom = inquiry::Zoom::Position.parse_value(&reply).ok_or(ViscaError::UnexpectedReply(reply))?;
+        Ok(Position { pan, tilt, zoom: zoom as u16 })
+    }
+
+    /// Plans a move from the current position to `to` taking about `duration`
+    pub fn plan_move(&mut self, table: &SpeedTable, to: Position, duration: Duration) -> Result<MovePlan> {
+        Ok(MovePlan::new(table, self.position()?, to, duration))
+    }
+
+    /// Moves to `to` with pan, tilt and zoom arriving together after about
+    /// `duration`, returning once the camera has settled on the target
+    pub fn move_to(&mut self, table: &SpeedTable, to: Position, duration: Duration) -> Result<()> {
+        let plan = self.plan_move(table, to, duration)?;
+        self.run_move(table, &plan)
+    }
+
+    /// Runs a planned move, see `move_to`. The camera should still be at
+    /// `plan.from`.
+    pub fn run_move(&mut self, table: &SpeedTable, plan: &MovePlan) -> Result<()> {
+        if let Err(e) = self.drive_plan(table, plan) {
+            // Don't leave the camera running if a position read or command failed
+            self.stop_moving();
+            return Err(e);
+        }
+        let speed = [plan.pan_speed, plan.tilt_speed].into_iter().flatten().max().unwrap_or(PanTiltSpeed::MIN);
+        self.execute(PanTilt::AbsolutePos(speed, plan.to.pan, plan.to.tilt), MOVE_TIMEOUT)?;
+        self.execute(Zoom::Direct(plan.to.zoom), MOVE_TIMEOUT)
+    }
+
+    /// Stops pan, tilt and zoom, ignoring failures; for error paths that
+    /// should not leave the camera moving
+    pub(crate) fn stop_moving(&mut self) {
+        let _ = self.set(PanTilt::Stop(PanTiltSpeed::MIN, PanTiltSpeed::MIN));
+        let _ = self.set(Zoom::Stop);
+    }
+
+    // The variable speed part of `run_move`, stopping each axis as it arrives
+    fn drive_plan(&mut self, table: &SpeedTable, plan: &MovePlan) -> Result<()> {
+        let (from, to) = (plan.from, plan.to);
+        let mut pan = plan.pan_speed.is_some();
+        let mut tilt = plan.tilt_speed.is_some();
+        let mut zoom = plan.zoom_speed.is_some();
+
+        if pan || tilt {
+            self.set(plan.pan_tilt_command(pan, tilt)).map_err(ViscaError::CameraConnectionError)?;
+        }
+        if zoom {
+            self.set(plan.zoom_command()).map_err(ViscaError::CameraConnectionError)?;
+        }
+
+        // Give up on the variable speed part if it runs well over, the final
+        // absolute move still gets the camera to the target
+        let deadline = Instant::now() + plan.duration * 2 + Duration::from_secs(1);
+        while pan || tilt || zoom {
+            thread::sleep(POLL_INTERVAL);
+            let now = self.position()?;
+            let late = Instant::now() >= deadline;
+
+            let pan_rate = plan.pan_speed.map_or(0.0, |s| table.pan_rate(s)) * UNITS_PER_DEGREE;
+            let tilt_rate = plan.tilt_speed.map_or(0.0, |s| table.tilt_rate(s)) * UNITS_PER_DEGREE;
+            let pan_done = pan && (late || arriving(pan_signed(from.pan), pan_signed(to.pan), pan_signed(now.pan), pan_rate));
+            let tilt_done = tilt
+                && (late || arriving(from.tilt as i16 as i32, to.tilt as i16 as i32, now.tilt as i16 as i32, tilt_rate));
+            if pan_done || tilt_done {
+                pan &= !pan_done;
+                tilt &= !tilt_done;
+                self.set(plan.pan_tilt_command(pan, tilt)).map_err(ViscaError::CameraConnectionError)?;
+            }
+
+            let zoom_rate = plan.zoom_speed.map_or(0.0, |s| table.zoom_rate(s));
+            if zoom && (late || arriving(from.zoom as i32, to.zoom as i32, now.zoom as i32, zoom_rate)) {
+                zoom = false;
+                self.set(Zoom::Stop).map_err(ViscaError::CameraConnectionError)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Camera {
+    /// Measures how fast each axis moves at each speed setting. Every speed
+    /// runs from centre (pan/tilt) or wide (zoom) for up to `interval` once
+    /// up to speed, less for fast speeds that would otherwise reach the end
+    /// of travel, with the position read at either end of the interval.
+    /// Takes a few minutes and moves the camera through much of its range.
+    pub fn measure_speeds(&mut self, interval: Duration) -> Result<SpeedTable> {
+        let nominal = SpeedTable::default();
+        let mut table = SpeedTable::default();
+        let slow = PanTiltSpeed::MIN;
+        let start = |camera: &mut Camera| {
+            camera.execute(PanTilt::AbsolutePos(PanTiltSpeed::MAX, 0, 0), MOVE_TIMEOUT)?;
+            camera.execute(Zoom::Direct(0), MOVE_TIMEOUT)
+        };
+
+        for value in PanTiltSpeed::MIN.value()..=PanTiltSpeed::MAX.value() {
+            let speed = PanTiltSpeed::clamped(value);
+            let i = (value - PanTiltSpeed::MIN.value()) as usize;
+
+            start(self)?;
+            let time = interval.min(Duration::from_secs_f32(MAX_PAN_TRAVEL / nominal.pan[i]));
+            let moving = PanTilt::Left(speed, slow).into();
+            table.pan[i] = self.measure_rate(moving, PanTilt::Stop(slow, slow).into(), time, |p| pan_degrees(p.pan))?;
+
+            start(self)?;
+            let time = interval.min(Duration::from_secs_f32(MAX_TILT_TRAVEL / nominal.tilt[i]));
+            let moving = PanTilt::Up(slow, speed).into();
+            table.tilt[i] = self.measure_rate(moving, PanTilt::Stop(slow, slow).into(), time, |p| tilt_degrees(p.tilt))?;
+        }
+
+        for value in VarSpeed::MIN.value()..=VarSpeed::MAX.value() {
+            let i = value as usize;
+            start(self)?;
+            let time = interval.min(Duration::from_secs_f32(MAX_ZOOM_TRAVEL / nominal.zoom[i]));
+            let moving = Zoom::TeleVar(VarSpeed::clamped(value)).into();
+            table.zoom[i] = self.measure_rate(moving, Zoom::Stop.into(), time, |p| p.zoom as f32)?;
+        }
+
+        start(self)?;
+        Ok(table)
+    }
+
+    // Units per second covered by `axis` while `moving` runs at full speed
+    fn measure_rate(&mut self, moving: Command, stop: Command, time: Duration, axis: fn(&Position) -> f32) -> Result<f32> {
+        self.set(moving).map_err(ViscaError::CameraConnectionError)?;
+        let measured = (|| {
+            thread::sleep(RAMP);
+            let t1 = Instant::now();
+            let p1 = axis(&self.position()?);
+            thread::sleep(time);
+            let t2 = Instant::now();
+            let p2 = axis(&self.position()?);
+            Ok((p2 - p1).abs() / (t2 - t1).as_secs_f32())
+        })();
+        // Always stop, even if reading the position failed
+        self.set(stop).map_err(ViscaError::CameraConnectionError)?;
+        let rate = measured?;
+        if rate > 0.0 { Ok(rate) } else { Err(ViscaError::ParameterOutOfRange("measured speed")) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_speeds_and_timing() {
+        let table = SpeedTable::default();
+        let from = Position::default();
+        let to = Position { pan: pan_position(10.0), tilt: 0, zoom: 0x4000 };
+        let plan = MovePlan::new(&table, from, to, Duration::from_secs(5));
+
+        // 2 degrees a second is nearest to 2.15 at speed 08, and 3277 zoom
+        // positions a second to 2916 at speed 4
+        assert_eq!(plan.pan_speed, Some(PanTiltSpeed::new(0x08).unwrap()));
+        assert_eq!(plan.tilt_speed, None);
+        assert_eq!(plan.zoom_speed, Some(VarSpeed::new(4).unwrap()));
+        // zoom is the slowest to arrive
+        assert!((plan.duration.as_secs_f32() - 0x4000 as f32 / 2916.0).abs() < 0.01);
+        assert_eq!(plan.pan_tilt_command(true, false), PanTilt::Left(plan.pan_speed.unwrap(), PanTiltSpeed::MIN));
+        assert_eq!(plan.zoom_command(), Zoom::TeleVar(VarSpeed::new(4).unwrap()));
+
+        let back = MovePlan::new(&table, to, Position { tilt: tilt_position(-5.0), ..to }, Duration::from_secs(1));
+        assert_eq!((back.pan_speed, back.zoom_speed), (None, None));
+        // 5 degrees a second is nearest to 4.94 at speed 0C
+        assert_eq!(back.tilt_speed, Some(PanTiltSpeed::new(0x0C).unwrap()));
+        assert!(matches!(back.pan_tilt_command(false, true), PanTilt::Down(..)));
+
+        let still = MovePlan::new(&table, from, from, Duration::from_secs(2));
+        assert_eq!(still.duration, Duration::ZERO);
+    }
+
+    #[test]
+    fn speed_table_file() {
+        let mut table = SpeedTable::default();
+        table.pan[0] = 0.45;
+        table.zoom[7] = 9876.5;
+        let text = table.to_string();
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.starts_with("pan = 0.45 0.62 "));
+        assert!(text.ends_with(" 6737 9876.5\n"));
+        assert_eq!(text.parse::<SpeedTable>().unwrap(), table);
+
+        let path = std::env::temp_dir().join(format!("visca_ip_speeds_{}.txt", std::process::id()));
+        table.save(&path).unwrap();
+        let loaded = SpeedTable::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), table);
+
+        // an axis left out keeps the nominal figures
+        let zoom_only = "# measured\nzoom = 1 2 3 4 5 6 7 8\n".parse::<SpeedTable>().unwrap();
+        assert_eq!(zoom_only.zoom, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(zoom_only.pan, NOMINAL_PAN_TILT);
+
+        assert!("zoom = 1 2 3\n".parse::<SpeedTable>().is_err());
+        assert!("zoom = 1 2 3 4 5 6 7 0\n".parse::<SpeedTable>().is_err());
+        assert!("roll = 1\n".parse::<SpeedTable>().is_err());
+    }
+}
@@ -0,0 +1,245 @@
+// Conversions between zoom/focus positions and optical quantities.
+//
+// `Zoom::Direct` takes a position from 0000 (wide) to 4000 (optical tele),
+// continuing to 6000 with Clear Image Zoom, and `Focus::Direct` a position from
+// 0000 (far) to F000 (near). A `Lens` holds a per-model table of positions
+// against magnification and focus distance and interpolates between them.
+// The built in tables are nominal; a table measured on a real unit can be
+// supplied with `Lens::new`.
+
+use crate::commands::{Focus, Zoom};
+use crate::{Result, ViscaError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    BrcX1000,
+    BrcH800,
+}
+
+// Zoom position against magnification, 12x optical then Clear Image Zoom
+const ZOOM_12X: [(u16, f32); 10] = [
+    (0x0000, 1.0),
+    (0x1000, 1.6),
+    (0x2000, 2.6),
+    (0x2800, 3.5),
+    (0x3000, 5.0),
+    (0x3800, 7.5),
+    (0x3C00, 9.5),
+    (0x4000, 12.0),
+    (0x5580, 18.0), // Clear Image Zoom limit for 4K
+    (0x6000, 24.0), // Clear Image Zoom limit for HD
+];
+
+// Focus position against subject distance in metres, 0000 is infinity
+const FOCUS_12X: [(u16, f32); 10] = [
+    (0x0000, f32::INFINITY),
+    (0x1000, 20.0),
+    (0x2000, 10.0),
+    (0x4000, 5.0),
+    (0x6000, 3.0),
+    (0x8000, 2.0),
+    (0xA000, 1.5),
+    (0xC000, 1.0),
+    (0xE000, 0.5),
+    (0xF000, 0.1),
+];
+
+const WIDE_HFOV_12X: f32 = 71.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lens {
+    zoom: Vec<(u16, f32)>,  // position, magnification
+    focus: Vec<(u16, f32)>, // position, distance (m)
+    wide_hfov: f32,         // horizontal field of view at 1x, degrees
+}
+
+impl Lens {
+    pub fn for_model(model: Model) -> Lens {
+        match model {
+            // Both models use the same 12x lens
+            Model::BrcX1000 | Model::BrcH800 => Lens {
+                zoom: ZOOM_12X.to_vec(),
+                focus: FOCUS_12X.to_vec(),
+                wide_hfov: WIDE_HFOV_12X,
+            },
+        }
+    }
+
+    /// Builds a lens from measured tables. Positions must increase, with
+    /// magnification increasing and focus distance decreasing along them.
+    pub fn new(zoom: Vec<(u16, f32)>, focus: Vec<(u16, f32)>, wide_hfov: f32) -> Result<Lens> {
+        let increasing = |table: &[(u16, f32)], rising: bool| {
+            table.len() >= 2
+                && table.windows(2).all(|w| {
+                    w[0].0 < w[1].0 && if rising { w[0].1 < w[1].1 } else { w[0].1 > w[1].1 }
+                })
+        };
+        if !increasing(&zoom, true) || zoom[0].1 <= 0.0 {
+            return Err(ViscaError::ParameterOutOfRange("zoom table"));
+        }
+        if !increasing(&focus, false) || focus[focus.len() - 1].1 <= 0.0 {
+            return Err(ViscaError::ParameterOutOfRange("focus table"));
+        }
+        if !(0.0..180.0).contains(&wide_hfov) {
+            return Err(ViscaError::ParameterOutOfRange("wide_hfov"));
+        }
+        Ok(Lens { zoom, focus, wide_hfov })
+    }
+
+    pub fn max_magnification(&self) -> f32 {
+        self.zoom[self.zoom.len() - 1].1
+    }
+
+    // Magnification is interpolated on a log scale, which tracks the
+    // zoom curve far better than a linear one between table points
+    pub fn magnification(&self, position: u16) -> f32 {
+        let log = self.zoom.iter().map(|&(p, m)| (p, m.ln())).collect::<Vec<_>>();
+        interpolate(&log, position).exp()
+    }
+
+    pub fn zoom_position(&self, magnification: f32) -> Result<u16> {
+        if !(self.zoom[0].1..=self.max_magnification()).contains(&magnification) {
+            return Err(ViscaError::ParameterOutOfRange("magnification"));
+        }
+        let log = self.zoom.iter().map(|&(p, m)| (p, m.ln())).collect::<Vec<_>>();
+        Ok(inverse(&log, magnification.ln()))
+    }
+
+    // Horizontal field of view in degrees
+    pub fn hfov(&self, position: u16) -> f32 {
+        let half = (self.wide_hfov / 2.0).to_radians().tan() / self.magnification(position);
+        2.0 * half.atan().to_degrees()
+    }
+
+    pub fn zoom_position_for_hfov(&self, degrees: f32) -> Result<u16> {
+        if !(0.0..180.0).contains(&degrees) || degrees == 0.0 {
+            return Err(ViscaError::ParameterOutOfRange("hfov"));
+        }
+        let wide = (self.wide_hfov / 2.0).to_radians().tan();
+        let magnification = wide / (degrees / 2.0).to_radians().tan();
+        self.zoom_position(magnification)
+    }
+
+    // Subject distance in metres, infinite at the far end
+    pub fn focus_distance(&self, position: u16) -> f32 {
+        // Focus moves close to linearly in dioptres (1 / distance)
+        let dioptres = self.focus.iter().map(|&(p, d)| (p, 1.0 / d)).collect::<Vec<_>>();
+        1.0 / interpolate(&dioptres, position)
+    }
+
+    pub fn focus_position(&self, metres: f32) -> Result<u16> {
+        let nearest = self.focus[self.focus.len() - 1].1;
+        if metres.is_nan() || metres < nearest {
+            return Err(ViscaError::ParameterOutOfRange("focus distance"));
+        }
+        let dioptres = self.focus.iter().map(|&(p, d)| (p, 1.0 / d)).collect::<Vec<_>>();
+        Ok(inverse(&dioptres, 1.0 / metres))
+    }
+
+    pub fn zoom_to(&self, magnification: f32) -> Result<Zoom> {
+        self.zoom_position(magnification).map(Zoom::Direct)
+    }
+
+    pub fn zoom_to_hfov(&self, degrees: f32) -> Result<Zoom> {
+        self.zoom_position_for_hfov(degrees).map(Zoom::Direct)
+    }
+
+    pub fn focus_at(&self, metres: f32) -> Result<Focus> {
+        self.focus_position(metres).map(Focus::Direct)
+    }
+}
+
+// Linear interpolation of the value at `position`, clamped to the table
+fn interpolate(table: &[(u16, f32)], position: u16) -> f32 {
+    if position <= table[0].0 {
+        return table[0].1;
+    }
+    for w in table.windows(2) {
+        let ((p0, v0), (p1, v1)) = (w[0], w[1]);
+        if position <= p1 {
+            let t = (position - p0) as f32 / (p1 - p0) as f32;
+            return v0 + t * (v1 - v0);
+        }
+    }
+    table[table.len() - 1].1
+}
+
+// Position giving `value`, for a table whose values are monotonic
+fn inverse(table: &[(u16, f32)], value: f32) -> u16 {
+    let rising = table[0].1 < table[table.len() - 1].1;
+    for w in table.windows(2) {
+        let ((p0, v0), (p1, v1)) = (w[0], w[1]);
+        let within = if rising { value <= v1 } else { value >= v1 };
+        if within {
+            let t = ((value - v0) / (v1 - v0)).clamp(0.0, 1.0);
+            return (p0 as f32 + t * (p1 - p0) as f32).round() as u16;
+        }
+    }
+    table[table.len() - 1].0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lens() -> Lens {
+        Lens::for_model(Model::BrcX1000)
+    }
+
+    #[test]
+    fn magnification_round_trip() {
+        let lens = lens();
+        assert_eq!(lens.magnification(0x4000), 12.0);
+        // halfway between 1.6x and 2.6x on a log scale
+        assert!((lens.magnification(0x1800) - (1.6f32 * 2.6).sqrt()).abs() < 1e-4);
+        assert_eq!(lens.magnification(0x7000), 24.0);
+
+        for position in (0..=0x6000).step_by(0x0123) {
+            let back = lens.zoom_position(lens.magnification(position)).unwrap();
+            assert!(back.abs_diff(position) <= 1, "{:04X} came back as {:04X}", position, back);
+        }
+        assert!(lens.zoom_position(0.5).is_err());
+        assert!(lens.zoom_position(30.0).is_err());
+
+        let hfov = lens.hfov(0x3000);
+        assert!(lens.zoom_position_for_hfov(hfov).unwrap().abs_diff(0x3000) <= 1);
+        assert_eq!(lens.zoom_to(1.0).unwrap(), Zoom::Direct(0));
+    }
+
+    #[test]
+    fn focus_round_trip() {
+        let lens = lens();
+        assert_eq!(lens.focus_distance(0), f32::INFINITY);
+        // halfway between 10 m and 5 m in dioptres
+        assert!((lens.focus_distance(0x3000) - 1.0 / 0.15).abs() < 1e-3);
+
+        for position in (0..=0xF000).step_by(0x0456) {
+            let back = lens.focus_position(lens.focus_distance(position)).unwrap();
+            assert!(back.abs_diff(position) <= 1, "{:04X} came back as {:04X}", position, back);
+        }
+        assert_eq!(lens.focus_position(f32::INFINITY).unwrap(), 0);
+        assert!(lens.focus_position(0.05).is_err());
+        assert!(lens.focus_position(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn new_validates_tables() {
+        let zoom = vec![(0x0000, 1.0), (0x4000, 12.0)];
+        let focus = vec![(0x0000, f32::INFINITY), (0xF000, 0.1)];
+        assert!(Lens::new(zoom.clone(), focus.clone(), 71.0).is_ok());
+
+        assert!(Lens::new(vec![(0x0000, 1.0)], focus.clone(), 71.0).is_err());
+        assert!(Lens::new(Vec::new(), focus.clone(), 71.0).is_err());
+        assert!(Lens::new(vec![(0x4000, 1.0), (0x0000, 12.0)], focus.clone(), 71.0).is_err());
+        assert!(Lens::new(vec![(0x0000, 12.0), (0x4000, 1.0)], focus.clone(), 71.0).is_err());
+        assert!(Lens::new(vec![(0x0000, 0.0), (0x4000, 12.0)], focus.clone(), 71.0).is_err());
+
+        assert!(Lens::new(zoom.clone(), vec![(0x0000, 1.0)], 71.0).is_err());
+        assert!(Lens::new(zoom.clone(), vec![(0xF000, 10.0), (0x0000, 1.0)], 71.0).is_err());
+        assert!(Lens::new(zoom.clone(), vec![(0x0000, 1.0), (0xF000, 10.0)], 71.0).is_err());
+        assert!(Lens::new(zoom.clone(), vec![(0x0000, 1.0), (0xF000, 0.0)], 71.0).is_err());
+
+        assert!(Lens::new(zoom, focus, 180.0).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+// Host side presets.
+//
+// The camera's own memories (`commands::Preset`) are limited in number and
+// stay with the camera. A `PresetStore` keeps any number of named presets as
+// `CameraState`s instead: pan/tilt, zoom and focus, plus the exposure and
+// colour settings when recorded with `picture`. Recalling one goes through
+// `Camera::restore`, so a store can be used with any camera of the same model.
+//
+// The file format is a `[name]` line per preset followed by its state in the
+// `CameraState` `key = value` format.
+//
+// `CameraPresets` copies the camera's own memories instead, for moving them
+// to a replacement unit: each slot is recalled and the position read back,
+// then on the new camera the position is restored and stored with
+// `Preset::Set`. Its file uses the slot number as the section name and adds a
+// `speed` line for the slot's `Preset::PresetDriveSpeed`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::commands::{Command, Preset};
+use crate::inquiry;
+use crate::params::{Code, PanTiltSpeed, PresetNumber};
+use crate::state::MOVE_TIMEOUT;
+use crate::{Camera, CameraState, Result, ViscaError, ViscaInquiry};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresetStore {
+    presets: BTreeMap<String, CameraState>,
+}
+
+impl PresetStore {
+    pub fn new() -> Self {
+        PresetStore::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Reads the camera's position into preset `name`, along with its
+    /// exposure and colour settings if `picture` is set
+    pub fn record(&mut self, camera: &mut Camera, name: &str, picture: bool) -> Result<()> {
+        let state = if picture { camera.snapshot()? } else { CameraState::read_position(camera)? };
+        self.insert(name, state)
+    }
+
+    /// Moves the camera to preset `name`, returning false if there is none
+    pub fn recall(&self, camera: &mut Camera, name: &str) -> Result<bool> {
+        match self.presets.get(name) {
+            Some(state) => camera.restore(state).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, state: CameraState) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['[', ']', '\n']) {
+            return Err(ViscaError::ParameterOutOfRange("preset name"));
+        }
+        self.presets.insert(name.to_string(), state);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CameraState> {
+        self.presets.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<CameraState> {
+        self.presets.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+}
+
+impl fmt::Display for PresetStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, state) in &self.presets {
+            writeln!(f, "[{}]", name)?;
+            write!(f, "{}", state)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PresetStore {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut store = PresetStore::default();
+        for (name, body) in sections(s)? {
+            store.insert(name, body.parse()?)?;
+        }
+        Ok(store)
+    }
+}
+
+// One of the camera's preset memories
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlotPreset {
+    pub speed: Option<PanTiltSpeed>, // drive speed used when the slot is recalled
+    pub state: CameraState,          // position only
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraPresets {
+    slots: BTreeMap<PresetNumber, SlotPreset>,
+}
+
+impl CameraPresets {
+    /// Recalls each of `slots` in turn and reads back where the camera went.
+    /// Slots the camera refuses to recall are left out. This moves the camera.
+    pub fn read(camera: &mut Camera, slots: impl IntoIterator<Item = PresetNumber>) -> Result<Self> {
+        let mut presets = CameraPresets::default();
+        for slot in slots {
+            match camera.execute(Preset::Recall(slot), MOVE_TIMEOUT) {
+                Ok(()) => {}
+                Err(e @ ViscaError::CameraConnectionError(_)) => return Err(e),
+                Err(_) => continue,
+            }
+            let inquiry = inquiry::Preset::DriveSpeed(slot);
+            let speed = match camera.inquire(&inquiry) {
+                Ok(reply) => inquiry.parse_value(&reply).and_then(|v| PanTiltSpeed::from_code(v).ok()),
+                Err(e @ ViscaError::CameraConnectionError(_)) => return Err(e),
+                Err(_) => None,
+            };
+            let state = CameraState::read_position(camera)?;
+            presets.slots.insert(slot, SlotPreset { speed, state });
+        }
+        Ok(presets)
+    }
+
+    /// The commands that store these presets on a camera, in order
+    pub fn commands(&self) -> Vec<Command> {
+        let mut out = Vec::new();
+        for (&slot, preset) in &self.slots {
+            out.extend(preset.state.commands());
+            out.push(Preset::Set(slot).into());
+            out.extend(preset.speed.map(|speed| Preset::PresetDriveSpeed(slot, speed).into()));
+        }
+        out
+    }
+
+    /// Stores every preset in the camera's memories, overwriting what is
+    /// there, and returns the commands sent. With `dry_run` nothing is sent
+    /// and the commands that would be are returned.
+    pub fn write(&self, camera: &mut Camera, dry_run: bool) -> Result<Vec<Command>> {
+        let commands = self.commands();
+        if !dry_run {
+            for command in &commands {
+                camera.execute(command.clone(), MOVE_TIMEOUT)?;
+            }
+        }
+        Ok(commands)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn get(&self, slot: PresetNumber) -> Option<&SlotPreset> {
+        self.slots.get(&slot)
+    }
+
+    pub fn insert(&mut self, slot: PresetNumber, preset: SlotPreset) {
+        self.slots.insert(slot, preset);
+    }
+
+    pub fn remove(&mut self, slot: PresetNumber) -> Option<SlotPreset> {
+        self.slots.remove(&slot)
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = PresetNumber> + '_ {
+        self.slots.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+impl fmt::Display for CameraPresets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (slot, preset) in &self.slots {
+            writeln!(f, "[{}]", slot.value())?;
+            if let Some(speed) = preset.speed {
+                writeln!(f, "speed = {:X}", speed.value())?;
+            }
+            write!(f, "{}", preset.state)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CameraPresets {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut presets = CameraPresets::default();
+        for (name, body) in sections(s)? {
+            let slot = name.parse().map_err(|_| ViscaError::ParameterOutOfRange("preset slot"))?;
+            let slot = PresetNumber::new(slot)?;
+            let mut preset = SlotPreset::default();
+            let mut state = String::new();
+            for line in body.lines() {
+                match line.split_once('=') {
+                    Some((key, value)) if key.trim() == "speed" => {
+                        let value = u8::from_str_radix(value.trim(), 16)
+                            .map_err(|_| ViscaError::ParameterOutOfRange("preset speed"))?;
+                        preset.speed = Some(PanTiltSpeed::new(value)?);
+                    }
+                    _ => {
+                        state.push_str(line);
+                        state.push('\n');
+                    }
+                }
+            }
+            preset.state = state.parse()?;
+            presets.slots.insert(slot, preset);
+        }
+        Ok(presets)
+    }
+}
+
+// Splits a file into `[name]` sections, each with the text that follows it
+fn sections(s: &str) -> Result<Vec<(&str, String)>> {
+    let mut out: Vec<(&str, String)> = Vec::new();
+    for line in s.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            out.push((name.trim(), String::new()));
+        } else if let Some((_, body)) = out.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        } else if !line.is_empty() && !line.starts_with('#') {
+            return Err(ViscaError::ParameterOutOfRange("preset line"));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{PanTilt, Zoom};
+    use crate::params::{ExposureMode, Iris};
+
+    fn position(pan: u32, tilt: u16, zoom: u16) -> CameraState {
+        CameraState { pan: Some(pan), tilt: Some(tilt), zoom_position: Some(zoom), ..CameraState::default() }
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let mut store = PresetStore::new();
+        store.insert("stage left", position(0x1234, 0xFF00, 0x4000)).unwrap();
+        let mut picture = position(0, 0, 0);
+        picture.exposure_mode = Some(ExposureMode::Manual);
+        picture.iris = Some(Iris::new(0x0C).unwrap());
+        store.insert("lectern", picture).unwrap();
+
+        let text = store.to_string();
+        assert!(text.starts_with("[lectern]\n"));
+        assert!(text.contains("[stage left]\n"));
+        assert_eq!(text.parse::<PresetStore>().unwrap(), store);
+
+        assert!(store.insert("a [b]", CameraState::default()).is_err());
+        assert!("pan = 1\n[wide]\n".parse::<PresetStore>().is_err());
+    }
+
+    #[test]
+    fn slots_round_trip() {
+        let mut presets = CameraPresets::default();
+        let state = position(0x10, 0x20, 0x30);
+        let speed = Some(PanTiltSpeed::new(0x0C).unwrap());
+        presets.insert(PresetNumber::new(2).unwrap(), SlotPreset { speed, state: state.clone() });
+        presets.insert(PresetNumber::new(0x0A).unwrap(), SlotPreset { speed: None, state });
+
+        let text = presets.to_string();
+        assert!(text.starts_with("[2]\nspeed = C\n"));
+        assert!(text.contains("[10]\n"));
+        assert_eq!(text.parse::<CameraPresets>().unwrap(), presets);
+
+        assert!("[x]\npan = 1\n".parse::<CameraPresets>().is_err());
+        assert!("[1]\nspeed = 40\n".parse::<CameraPresets>().is_err());
+    }
+
+    #[test]
+    fn dry_run_sends_nothing() {
+        let slot = PresetNumber::new(3).unwrap();
+        let speed = PanTiltSpeed::new(0x10).unwrap();
+        let mut presets = CameraPresets::default();
+        presets.insert(slot, SlotPreset { speed: Some(speed), state: position(0x100, 0x200, 0x300) });
+
+        let mut camera = Camera::new("127.0.0.1:9").unwrap();
+        let commands = presets.write(&mut camera, true).unwrap();
+        let expected: Vec<Command> = vec![
+            Zoom::Direct(0x300).into(),
+            PanTilt::AbsolutePos(PanTiltSpeed::MAX, 0x100, 0x200).into(),
+            Preset::Set(slot).into(),
+            Preset::PresetDriveSpeed(slot, speed).into(),
+        ];
+        assert_eq!(commands, expected);
+        assert_eq!((camera.queue.depth(), camera.queue.in_flight()), (0, 0));
+    }
+}
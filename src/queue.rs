@@ -0,0 +1,325 @@
+// Host side scheduling of commands onto the camera's two command sockets.
+//
+// The camera accepts at most two commands at a time and answers a third with
+// "command buffer full". Commands are held here until a socket frees, and a
+// command that is bounced with a buffer full error goes back to the head of the
+// queue instead of being lost.
+
+use std::collections::VecDeque;
+
+use crate::messages::{self as msg, MessageType, Reply};
+use crate::{Result, ViscaError};
+
+/// Number of command sockets on the camera
+pub const SOCKETS: usize = 2;
+
+/// Identifies a command submitted with `Camera::submit`
+pub type Ticket = u64;
+
+/// The outcome of a submitted command, returned by `Camera::poll`.
+/// `reply` holds the raw reply payload (including inquiry data, if any).
+#[derive(Debug)]
+pub struct Finished {
+    pub ticket: Ticket,
+    pub reply: Result<Vec<u8>>,
+}
+
+// How a command left the queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Done {
+    Completed(Ticket, Vec<u8>), // the command
+    Failed(Ticket, u8),         // VISCA error code
+}
+
+#[derive(Debug)]
+pub(crate) struct Pending {
+    pub ticket: Ticket,
+    pub msg_type: MessageType,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    pending: Pending,
+    seqnum: u32,
+    socket: Option<u8>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CommandQueue {
+    next_ticket: Ticket,
+    waiting: VecDeque<Pending>,
+    in_flight: Vec<InFlight>,
+    finished: Vec<Finished>,
+    // Set when the camera reports a full buffer, cleared by the next completion.
+    // Protects against spinning when sockets are held by commands sent with `set`.
+    blocked: bool,
+}
+
+impl CommandQueue {
+    pub fn pending(&mut self, msg_type: MessageType, bytes: Vec<u8>) -> Pending {
+        let ticket = self.next_ticket;
+        self.next_ticket = self.next_ticket.wrapping_add(1);
+        Pending { ticket, msg_type, bytes }
+    }
+
+    pub fn push(&mut self, pending: Pending) {
+        self.waiting.push_back(pending);
+    }
+
+    /// Commands waiting for a free socket
+    pub fn depth(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Commands sent to the camera that have not completed
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    fn sockets_in_use(&self) -> usize {
+        self.in_flight
+            .iter()
+            .filter(|f| f.pending.msg_type != MessageType::Inquiry)
+            .count()
+    }
+
+    /// Takes the next waiting command if a socket is free for it
+    pub fn next_ready(&mut self) -> Option<Pending> {
+        let inquiry = self.waiting.front()?.msg_type == MessageType::Inquiry;
+        // Inquiries are answered without occupying a command socket
+        if inquiry || (!self.blocked && self.sockets_in_use() < SOCKETS) {
+            self.waiting.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn sent(&mut self, pending: Pending, seqnum: u32) {
+        if msg::expects_reply(&pending.bytes) {
+            self.in_flight.push(InFlight { pending, seqnum, socket: None });
+        } else {
+            self.finished.push(Finished { ticket: pending.ticket, reply: Ok(Vec::new()) });
+        }
+    }
+
+    /// Stops tracking a command, whether or not it has been sent. A command the
+    /// camera never completes would otherwise hold its socket here forever.
+    pub fn withdraw(&mut self, ticket: Ticket) {
+        self.waiting.retain(|p| p.ticket != ticket);
+        self.in_flight.retain(|f| f.pending.ticket != ticket);
+    }
+
+    /// Drops every waiting command, returning their tickets
+    pub fn clear(&mut self) -> Vec<Ticket> {
+        self.waiting.drain(..).map(|p| p.ticket).collect()
+    }
+
+    /// Forgets the commands the camera was executing when it restarted. They
+    /// finish as cancelled and their sockets count as free again. Returns the
+    /// commands, as for `handle_reply`.
+    pub fn restarted(&mut self) -> Vec<Done> {
+        self.blocked = false;
+        let mut done = Vec::new();
+        for f in self.in_flight.drain(..) {
+            self.finished.push(Finished { ticket: f.pending.ticket, reply: Err(ViscaError::CommandCanceledError) });
+            if f.pending.msg_type == MessageType::Command {
+                done.push(Done::Failed(f.pending.ticket, 0x04));
+            }
+        }
+        done
+    }
+
+    /// Sockets the camera has acknowledged commands on
+    pub fn sockets(&self) -> Vec<u8> {
+        self.in_flight.iter().filter_map(|f| f.socket).collect()
+    }
+
+    /// Returns how a command ended if this reply ended one
+    pub fn handle_reply(&mut self, seqnum: u32, payload: &[u8]) -> Option<Done> {
+        let reply = Reply::parse(payload)?;
+
+        if matches!(reply, Reply::Completion(..)) {
+            self.blocked = false;
+        }
+
+        // Match on the echoed sequence number, falling back on the socket
+        let index = self.in_flight.iter().position(|f| f.seqnum == seqnum).or_else(|| {
+            let socket = match reply {
+                Reply::Ack(_) => return None,
+                Reply::Completion(s, _) | Reply::Error(s, _) => s,
+            };
+            self.in_flight.iter().position(|f| f.socket == Some(socket))
+        });
+        let index = index?;
+
+        let (msg_type, done) = match reply {
+            Reply::Ack(socket) => {
+                self.in_flight[index].socket = Some(socket);
+                return None;
+            }
+            Reply::Completion(..) => {
+                let done = self.in_flight.remove(index);
+                self.finished.push(Finished { ticket: done.pending.ticket, reply: Ok(payload.to_vec()) });
+                (done.pending.msg_type, Done::Completed(done.pending.ticket, done.pending.bytes))
+            }
+            Reply::Error(_, code) => {
+                let done = self.in_flight.remove(index);
+                match ViscaError::from_code(code) {
+                    ViscaError::CommandBufferFullError => {
+                        self.blocked = true;
+                        self.waiting.push_front(done.pending);
+                        return None;
+                    }
+                    e => self.finished.push(Finished { ticket: done.pending.ticket, reply: Err(e) }),
+                }
+                (done.pending.msg_type, Done::Failed(done.pending.ticket, code))
+            }
+        };
+        // Inquiries are answered through `Camera::inquire`, only commands are reported
+        (msg_type == MessageType::Command).then_some(done)
+    }
+
+    pub fn take_finished(&mut self, ticket: Ticket) -> Option<Result<Vec<u8>>> {
+        let index = self.finished.iter().position(|f| f.ticket == ticket)?;
+        Some(self.finished.remove(index).reply)
+    }
+
+    pub fn drain_finished(&mut self) -> Vec<Finished> {
+        std::mem::take(&mut self.finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: [u8; 6] = [0x81, 0x01, 0x04, 0x39, 0x03, 0xFF];
+    const INQUIRY: [u8; 5] = [0x81, 0x09, 0x04, 0x39, 0xFF];
+
+    // Queues a message and sends it as soon as the queue lets it go
+    fn send(queue: &mut CommandQueue, msg_type: MessageType, bytes: &[u8], seqnum: u32) -> Ticket {
+        let pending = queue.pending(msg_type, bytes.to_vec());
+        let ticket = pending.ticket;
+        queue.push(pending);
+        if let Some(pending) = queue.next_ready() {
+            queue.sent(pending, seqnum);
+        }
+        ticket
+    }
+
+    #[test]
+    fn ack_then_completion() {
+        let mut queue = CommandQueue::default();
+        let ticket = send(&mut queue, MessageType::Command, &COMMAND, 7);
+        assert_eq!(queue.handle_reply(7, &[0x90, 0x41, 0xFF]), None);
+        assert_eq!(queue.sockets(), [1]);
+        assert!(queue.take_finished(ticket).is_none());
+
+        assert_eq!(queue.handle_reply(7, &[0x90, 0x51, 0xFF]), Some(Done::Completed(ticket, COMMAND.to_vec())));
+        assert_eq!(queue.in_flight(), 0);
+        assert_eq!(queue.take_finished(ticket).unwrap().unwrap(), [0x90, 0x51, 0xFF]);
+    }
+
+    #[test]
+    fn completion_matched_by_socket() {
+        let mut queue = CommandQueue::default();
+        let first = send(&mut queue, MessageType::Command, &COMMAND, 1);
+        let second = send(&mut queue, MessageType::Command, &COMMAND, 2);
+        queue.handle_reply(1, &[0x90, 0x41, 0xFF]);
+        queue.handle_reply(2, &[0x90, 0x42, 0xFF]);
+
+        // Some cameras don't echo the sequence number of the command
+        assert_eq!(queue.handle_reply(0, &[0x90, 0x52, 0xFF]), Some(Done::Completed(second, COMMAND.to_vec())));
+        assert_eq!(queue.handle_reply(0, &[0x90, 0x61, 0x41, 0xFF]), Some(Done::Failed(first, 0x41)));
+        assert!(matches!(queue.take_finished(first), Some(Err(ViscaError::CommandNotExecutableError))));
+
+        // An ACK alone is never matched by socket
+        let third = send(&mut queue, MessageType::Command, &COMMAND, 3);
+        assert_eq!(queue.handle_reply(0, &[0x90, 0x41, 0xFF]), None);
+        assert!(queue.sockets().is_empty());
+        assert_eq!(queue.handle_reply(3, &[0x90, 0x61, 0x41, 0xFF]), Some(Done::Failed(third, 0x41)));
+    }
+
+    #[test]
+    fn inquiries_are_not_reported() {
+        let mut queue = CommandQueue::default();
+        let ticket = send(&mut queue, MessageType::Inquiry, &INQUIRY, 1);
+        assert_eq!(queue.handle_reply(1, &[0x90, 0x50, 0x03, 0xFF]), None);
+        assert_eq!(queue.take_finished(ticket).unwrap().unwrap(), [0x90, 0x50, 0x03, 0xFF]);
+    }
+
+    #[test]
+    fn waits_for_a_free_socket() {
+        let mut queue = CommandQueue::default();
+        send(&mut queue, MessageType::Command, &COMMAND, 1);
+        send(&mut queue, MessageType::Command, &COMMAND, 2);
+
+        // Inquiries don't take a socket
+        let inquiry = send(&mut queue, MessageType::Inquiry, &INQUIRY, 3);
+        assert_eq!((queue.in_flight(), queue.depth()), (3, 0));
+        queue.handle_reply(3, &[0x90, 0x50, 0x03, 0xFF]);
+        assert!(queue.take_finished(inquiry).is_some());
+
+        let third = send(&mut queue, MessageType::Command, &COMMAND, 4);
+        assert_eq!((queue.in_flight(), queue.depth()), (2, 1));
+        assert!(queue.next_ready().is_none());
+
+        queue.handle_reply(1, &[0x90, 0x51, 0xFF]);
+        assert_eq!(queue.next_ready().unwrap().ticket, third);
+    }
+
+    #[test]
+    fn buffer_full_requeues_and_blocks() {
+        let mut queue = CommandQueue::default();
+        let first = send(&mut queue, MessageType::Command, &COMMAND, 1);
+        let second = send(&mut queue, MessageType::Command, &COMMAND, 2);
+        let waiting = queue.pending(MessageType::Command, COMMAND.to_vec());
+        queue.push(waiting);
+
+        // The camera's sockets are held by something the queue doesn't know about
+        assert_eq!(queue.handle_reply(2, &[0x90, 0x60, 0x03, 0xFF]), None);
+        assert!(queue.take_finished(second).is_none());
+        assert_eq!((queue.in_flight(), queue.depth()), (1, 2));
+        assert!(queue.next_ready().is_none(), "blocked until a completion");
+
+        queue.handle_reply(1, &[0x90, 0x51, 0xFF]);
+        assert!(queue.take_finished(first).is_some());
+        // The bounced command goes first
+        assert_eq!(queue.next_ready().unwrap().ticket, second);
+    }
+
+    #[test]
+    fn cancel_finishes_when_sent() {
+        let mut queue = CommandQueue::default();
+        let ticket = send(&mut queue, MessageType::Command, &[0x81, 0x21, 0xFF], 1);
+        assert_eq!(queue.in_flight(), 0);
+        assert!(queue.take_finished(ticket).unwrap().is_ok());
+    }
+
+    #[test]
+    fn restart_cancels_in_flight() {
+        let mut queue = CommandQueue::default();
+        let command = send(&mut queue, MessageType::Command, &COMMAND, 1);
+        let inquiry = send(&mut queue, MessageType::Inquiry, &INQUIRY, 2);
+        queue.blocked = true;
+
+        assert_eq!(queue.restarted(), [Done::Failed(command, 0x04)]);
+        assert_eq!(queue.in_flight(), 0);
+        assert!(!queue.blocked);
+        assert!(matches!(queue.take_finished(inquiry), Some(Err(ViscaError::CommandCanceledError))));
+    }
+
+    #[test]
+    fn clear_and_withdraw() {
+        let mut queue = CommandQueue::default();
+        let sent = send(&mut queue, MessageType::Command, &COMMAND, 1);
+        send(&mut queue, MessageType::Command, &COMMAND, 2);
+        let waiting = send(&mut queue, MessageType::Command, &COMMAND, 3);
+
+        queue.withdraw(sent);
+        assert_eq!(queue.in_flight(), 1);
+        assert_eq!(queue.clear(), [waiting]);
+        assert_eq!(queue.depth(), 0);
+    }
+}
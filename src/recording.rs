@@ -0,0 +1,303 @@
+// Recording and replay of operator moves.
+//
+// While recording, every pan/tilt, zoom and focus command sent with
+// `Camera::set` is kept in a `Take` with the time it was sent, along with the
+// camera position, read every sample interval by a background thread on a
+// socket of its own so moves that run on without new commands are still
+// traced. A take is saved as
+// text, one `seconds kind data` line per event, and can be replayed on any
+// camera, slower or faster, optionally starting and finishing on the recorded
+// positions so a replay on a different unit ends up in the same place.
+// Replayed commands go through the geofence like any other, and the camera is
+// stopped if the replay fails part way.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::net::UdpSocket;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU32;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::commands::{PanTilt, Zoom};
+use crate::events::query;
+use crate::inquiry;
+use crate::messages::{self as msg, ViscaCommand, ViscaInquiry, ViscaMessage};
+use crate::motion::Position;
+use crate::params::PanTiltSpeed;
+use crate::state::MOVE_TIMEOUT;
+use crate::{watchdog, Camera, Result, ViscaError};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TakeEvent {
+    Command(Vec<u8>), // as sent to the camera
+    Position(Position),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Take {
+    pub events: Vec<(Duration, TakeEvent)>, // time from the start of the recording
+}
+
+pub(crate) struct Recorder {
+    start: Instant,
+    take: Arc<Mutex<Take>>, // shared with the sampler
+    sampler: Option<Sampler>,
+}
+
+// Reads the position every interval into the take until dropped
+struct Sampler {
+    shutdown: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Sampler {
+    fn spawn(
+        socket: UdpSocket,
+        seqnum: Arc<AtomicU32>,
+        interval: Duration,
+        start: Instant,
+        take: Arc<Mutex<Take>>,
+    ) -> Self {
+        let shutdown = Arc::new((Mutex::new(false), Condvar::new()));
+        let stop = Arc::clone(&shutdown);
+
+        let thread = thread::spawn(move || loop {
+            let (lock, condvar) = &*stop;
+            if *condvar.wait_timeout_while(lock.lock().unwrap(), interval, |s| !*s).unwrap().0 {
+                return;
+            }
+            // A missed sample only loses detail
+            let at = Instant::now();
+            let pan_tilt = query(&socket, &seqnum, &inquiry::PanTilt::Position.bytes());
+            let zoom = query(&socket, &seqnum, &inquiry::Zoom::Position.bytes());
+            let pan_tilt = pan_tilt.as_deref().and_then(inquiry::PanTilt::parse_position);
+            let zoom = zoom.and_then(|reply| inquiry::Zoom::Position.parse_value(&reply));
+            if let (Some((pan, tilt)), Some(zoom)) = (pan_tilt, zoom) {
+                let position = Position { pan, tilt, zoom: zoom as u16 };
+                take.lock().unwrap().events.push((at - start, TakeEvent::Position(position)));
+            }
+        });
+
+        Sampler { shutdown, thread: Some(thread) }
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shutdown;
+        *lock.lock().unwrap() = true;
+        condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// A recorded command as it is replayed. Treated as a movement so the
+// watchdog, if enabled, covers the replay.
+struct Recorded<'a>(&'a [u8]);
+
+impl ViscaMessage for Recorded<'_> {
+    fn bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn msg_type(&self) -> msg::MessageType {
+        msg::MessageType::Command
+    }
+}
+
+impl ViscaCommand for Recorded<'_> {
+    fn parse_reply(&self, bytes: &[u8]) -> String {
+        format!("Reply: {:02X?}", bytes)
+    }
+
+    fn is_motion(&self) -> bool {
+        true
+    }
+
+    fn is_ptzf(&self) -> bool {
+        true
+    }
+}
+
+impl Take {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |(t, _)| *t)
+    }
+
+    fn positions(&self) -> impl DoubleEndedIterator<Item = Position> + '_ {
+        self.events.iter().filter_map(|(_, e)| match e {
+            TakeEvent::Position(p) => Some(*p),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Take {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (time, event) in &self.events {
+            write!(f, "{:.3} ", time.as_secs_f64())?;
+            match event {
+                TakeEvent::Command(bytes) => {
+                    write!(f, "command")?;
+                    for b in bytes {
+                        write!(f, " {:02X}", b)?;
+                    }
+                    writeln!(f)?;
+                }
+                TakeEvent::Position(p) => writeln!(f, "position {:05X} {:04X} {:04X}", p.pan, p.tilt, p.zoom)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Take {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || ViscaError::ParameterOutOfRange("take line");
+        let mut take = Take::default();
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut fields = line.split_whitespace();
+            let time = fields.next().and_then(|t| t.parse::<f64>().ok()).filter(|t| *t >= 0.0).ok_or_else(invalid)?;
+            let hex = |f: &str| u32::from_str_radix(f, 16).map_err(|_| invalid());
+            let event = match fields.next() {
+                Some("command") => TakeEvent::Command(
+                    fields.map(|f| u8::from_str_radix(f, 16).map_err(|_| invalid())).collect::<Result<_>>()?,
+                ),
+                Some("position") => {
+                    let values = fields.map(hex).collect::<Result<Vec<_>>>()?;
+                    match values[..] {
+                        [pan, tilt, zoom] if tilt <= 0xFFFF && zoom <= 0xFFFF => {
+                            TakeEvent::Position(Position { pan, tilt: tilt as u16, zoom: zoom as u16 })
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => return Err(invalid()),
+            };
+            take.events.push((Duration::from_secs_f64(time), event));
+        }
+        Ok(take)
+    }
+}
+
+impl Camera {
+    /// Starts recording the pan/tilt, zoom and focus commands sent with `set`,
+    /// reading the position when recording starts and then every
+    /// `sample_interval` in the background
+    pub fn start_recording(&mut self, sample_interval: Duration) -> Result<()> {
+        let socket = self.side_socket()?;
+        let start = Instant::now();
+        let take = Arc::new(Mutex::new(Take::default()));
+        self.recorder = Some(Recorder { start, take: Arc::clone(&take), sampler: None });
+        self.record_position()?;
+        let sampler = Sampler::spawn(socket, Arc::clone(&self.seqnum), sample_interval, start, take);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.sampler = Some(sampler);
+        }
+        Ok(())
+    }
+
+    /// Ends the recording, reading the final position, and returns the take
+    pub fn stop_recording(&mut self) -> Result<Option<Take>> {
+        let Some(recorder) = &mut self.recorder else { return Ok(None) };
+        recorder.sampler = None;
+        self.record_position()?;
+        let Some(recorder) = self.recorder.take() else { return Ok(None) };
+        let mut take = recorder.take.lock().unwrap().clone();
+        // Samples are timed when read but added when answered
+        take.events.sort_by_key(|(time, _)| *time);
+        Ok(Some(take))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Adds the current position to the recording now, in addition to the
+    /// periodic samples
+    pub fn record_position(&mut self) -> Result<()> {
+        if self.recorder.is_none() {
+            return Ok(());
+        }
+        let position = self.position()?;
+        if let Some(recorder) = &self.recorder {
+            let time = recorder.start.elapsed();
+            recorder.take.lock().unwrap().events.push((time, TakeEvent::Position(position)));
+        }
+        Ok(())
+    }
+
+    // Called by `set` for every pan/tilt, zoom and focus command
+    pub(crate) fn record(&mut self, bytes: Vec<u8>) {
+        if let Some(recorder) = &self.recorder {
+            let time = recorder.start.elapsed();
+            recorder.take.lock().unwrap().events.push((time, TakeEvent::Command(bytes)));
+        }
+    }
+
+    /// Sends the commands of `take` at their recorded times multiplied by
+    /// `time_scale` (2.0 takes twice as long). Speeds are not scaled, so a
+    /// scaled replay covers a different distance. With `correct` the camera
+    /// first goes to the take's first recorded position and finishes on its
+    /// last. Everything is stopped at the end, or as soon as a command fails.
+    pub fn replay(&mut self, take: &Take, time_scale: f32, correct: bool) -> Result<()> {
+        if !(time_scale.is_finite() && time_scale > 0.0) {
+            return Err(ViscaError::ParameterOutOfRange("time scale"));
+        }
+        if correct {
+            if let Some(first) = take.positions().next() {
+                self.go_to_position(first)?;
+            }
+        }
+
+        let played = self.play_commands(take, time_scale);
+        // Every stop is sent even if one fails, and the first failure returned
+        let stops = watchdog::stop_bytes().map(|bytes| self.set(Recorded(&bytes)));
+        let stopped = stops.into_iter().collect::<io::Result<()>>().map_err(ViscaError::CameraConnectionError);
+        played.and(stopped)?;
+
+        if correct {
+            if let Some(last) = take.positions().next_back() {
+                self.go_to_position(last)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn play_commands(&mut self, take: &Take, time_scale: f32) -> Result<()> {
+        let start = Instant::now();
+        for (time, event) in &take.events {
+            if let TakeEvent::Command(bytes) = event {
+                let due = time.mul_f32(time_scale);
+                thread::sleep(due.saturating_sub(start.elapsed()));
+                self.set(Recorded(bytes)).map_err(ViscaError::CameraConnectionError)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn go_to_position(&mut self, position: Position) -> Result<()> {
+        self.execute(PanTilt::AbsolutePos(PanTiltSpeed::MAX, position.pan, position.tilt), MOVE_TIMEOUT)?;
+        self.execute(Zoom::Direct(position.zoom), MOVE_TIMEOUT)
+    }
+}
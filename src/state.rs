@@ -0,0 +1,468 @@
+// Camera state snapshots ("scene files").
+//
+// `Camera::snapshot` reads every setting that has an inquiry into a
+// `CameraState`, and `Camera::restore` sends the commands that recreate it.
+// A state is saved as plain `key = value` lines (hex values) via `Display` and
+// read back with `str::parse`. Settings the camera did not answer are `None`
+// and are left alone on restore. `diff` lists the settings two states
+// disagree on, e.g. to match cameras for a multicam shoot.
+//
+// Shutter positions mean different speeds at different frame rates. A state
+// records the rate given to `Camera::set_frame_rate`, and `restore` moves
+// the shutter settings to the nearest speeds if the camera runs at another.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::commands::{self, Command};
+use crate::inquiry;
+use crate::params::*;
+use crate::{Camera, Result, ViscaError, ViscaInquiry, ViscaMessage};
+
+// Long enough for a full speed pan across the whole range
+pub(crate) const MOVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+macro_rules! camera_state {
+    ($($field:ident: $ty:ty = $inquiry:expr,)+) => {
+        #[derive(Debug, Clone, Default, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct CameraState {
+            $(pub $field: Option<$ty>,)+
+            pub pan: Option<u32>,
+            pub tilt: Option<u16>,
+            pub frame_rate: Option<FrameRate>, // which shutter table the shutter positions index
+        }
+
+        impl CameraState {
+            // Reads every setting, or with `wanted` only those it has a value for
+            fn read(camera: &mut Camera, wanted: Option<&CameraState>) -> Result<Self> {
+                let mut state = CameraState::default();
+                $(if wanted.is_none_or(|w| w.$field.is_some()) {
+                    state.$field = answer(camera, &$inquiry)?;
+                })+
+                if wanted.is_none_or(|w| w.pan.is_some() && w.tilt.is_some()) {
+                    state.read_pan_tilt(camera)?;
+                }
+                Ok(state)
+            }
+
+            fn entries(&self) -> Vec<(&'static str, Option<u32>)> {
+                vec![
+                    $((stringify!($field), self.$field.map(Code::code)),)+
+                    ("pan", self.pan.map(Code::code)),
+                    ("tilt", self.tilt.map(Code::code)),
+                    ("frame_rate", self.frame_rate.map(Code::code)),
+                ]
+            }
+
+            // Whether `reply` is a valid answer to `inquiry`, for inquiries
+            // that read a setting. Used to check replies the state cache
+            // derives from commands.
+            pub(crate) fn valid_reply(inquiry: &[u8], reply: &[u8]) -> bool {
+                $(if $inquiry.bytes() == inquiry {
+                    return $inquiry.parse_value(reply).is_some_and(|v| <$ty as Code>::from_code(v).is_ok());
+                })+
+                false
+            }
+
+            fn set_entry(&mut self, key: &str, code: u32) -> Result<()> {
+                match key {
+                    $(stringify!($field) => self.$field = Some(Code::from_code(code)?),)+
+                    "pan" => self.pan = Some(Code::from_code(code)?),
+                    "tilt" => self.tilt = Some(Code::from_code(code)?),
+                    "frame_rate" => self.frame_rate = Some(Code::from_code(code)?),
+                    _ => return Err(ViscaError::ParameterOutOfRange("state key")),
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+// The frame rate given to the shutter inquiries only picks the table for
+// their `parse_reply` labels. The state keeps the positions, and `frame_rate`
+// the table they belong to.
+camera_state! {
+    exposure_mode: ExposureMode = inquiry::Exposure::Mode,
+    iris: Iris = inquiry::Exposure::Iris,
+    gain: Gain = inquiry::Exposure::Gain,
+    gain_limit: GainLimit = inquiry::Exposure::GainLimit,
+    gain_point: OnOff = inquiry::Exposure::GainPoint,
+    gain_point_position: GainPointPosition = inquiry::Exposure::GainPointPosition,
+    shutter: Shutter = inquiry::Exposure::Shutter(FrameRate::Fps59_94),
+    max_shutter: LimitShutter = inquiry::Exposure::MaxShutter(FrameRate::Fps59_94),
+    min_shutter: LimitShutter = inquiry::Exposure::MinShutter(FrameRate::Fps59_94),
+    ae_speed: AESpeed = inquiry::Exposure::AESpeed,
+    exp_comp: OnOff = inquiry::Exposure::ExpCompOnOff,
+    exp_comp_level: ExpComp = inquiry::Exposure::ExpCompLevel,
+    back_light: OnOff = inquiry::Exposure::BackLight,
+    spot_light: OnOff = inquiry::Exposure::SpotLight,
+    vis_enhance: OnOff = inquiry::Exposure::VisEnhanceOnOff,
+    vis_enhance_level: VisEnhance = inquiry::Exposure::VisEnhanceLevel,
+    low_light: OnOff = inquiry::Exposure::LowLightBasisBrightnessOnOff,
+    low_light_level: LowLightBasisBrightness = inquiry::Exposure::LowLightBasisBrightnessLevel,
+    wb_mode: WBMode = inquiry::Colour::WBMode,
+    r_gain: u8 = inquiry::Colour::RGain,
+    b_gain: u8 = inquiry::Colour::BGain,
+    wb_speed: WBSpeed = inquiry::Colour::Speed,
+    chroma_suppress: ChromaSuppress = inquiry::Colour::ChromaSuppress,
+    matrix: Matrix = inquiry::Colour::Matrix,
+    colour_level: ColourLevel = inquiry::Colour::Level,
+    colour_phase: ColourPhase = inquiry::Colour::Phase,
+    matrix_rg: MatrixCoefficient = inquiry::Colour::RG,
+    matrix_rb: MatrixCoefficient = inquiry::Colour::RB,
+    matrix_gr: MatrixCoefficient = inquiry::Colour::GR,
+    matrix_gb: MatrixCoefficient = inquiry::Colour::GB,
+    matrix_br: MatrixCoefficient = inquiry::Colour::BR,
+    matrix_bg: MatrixCoefficient = inquiry::Colour::BG,
+    detail_level: DetailLevel = inquiry::Detail::Level,
+    detail_mode: DetailMode = inquiry::Detail::Mode,
+    detail_bandwidth: DetailBandwidth = inquiry::Detail::Bandwidth,
+    detail_crispening: Crispening = inquiry::Detail::Crispening,
+    detail_hv_balance: HVBalance = inquiry::Detail::HVBalance,
+    detail_bw_balance: BWBalance = inquiry::Detail::BWBalance,
+    detail_limit: DetailLimit = inquiry::Detail::Limit,
+    detail_highlight: HighlightDetail = inquiry::Detail::HighlightDetail,
+    detail_superlow: SuperLow = inquiry::Detail::SuperLow,
+    knee: OnOff = inquiry::Knee::Setting,
+    knee_mode: KneeMode = inquiry::Knee::Mode,
+    knee_slope: KneeSlope = inquiry::Knee::Slope,
+    knee_point: KneePoint = inquiry::Knee::Point,
+    gamma_mode: GammaMode = inquiry::Gamma::Mode,
+    gamma_pattern: GammaPattern = inquiry::Gamma::Pattern,
+    gamma_offset: GammaOffset = inquiry::Gamma::Offset,
+    gamma_level: GammaLevel = inquiry::Gamma::Level,
+    black_gamma_level: BlackGammaLevel = inquiry::Gamma::BlackGammaLevel,
+    black_gamma_range: BlackGammaRange = inquiry::Gamma::BlackGammaRange,
+    black_level: BlackLevel = inquiry::Gamma::BlackLevel,
+    focus_mode: FocusMode = inquiry::Focus::Mode,
+    focus_position: u16 = inquiry::Focus::Position,
+    zoom_position: u16 = inquiry::Zoom::Position,
+}
+
+impl CameraState {
+    // Reads only what `position` keeps, which is much quicker than `read`
+    pub(crate) fn read_position(camera: &mut Camera) -> Result<Self> {
+        let mut state = CameraState {
+            focus_mode: answer(camera, &inquiry::Focus::Mode)?,
+            focus_position: answer(camera, &inquiry::Focus::Position)?,
+            zoom_position: answer(camera, &inquiry::Zoom::Position)?,
+            ..CameraState::default()
+        };
+        state.read_pan_tilt(camera)?;
+        Ok(state)
+    }
+
+    fn read_pan_tilt(&mut self, camera: &mut Camera) -> Result<()> {
+        if let Some(reply) = answered(camera.inquire(&inquiry::PanTilt::Position))? {
+            if let Some((pan, tilt)) = inquiry::PanTilt::parse_position(&reply) {
+                self.pan = Some(pan);
+                self.tilt = Some(tilt);
+            }
+        }
+        Ok(())
+    }
+}
+
+// A setting the camera can't report (unsupported or not executable in the
+// current mode) is left out of the state; a timeout or a broken connection
+// fails the snapshot
+fn answered(reply: Result<Vec<u8>>) -> Result<Option<Vec<u8>>> {
+    match reply {
+        Ok(reply) => Ok(Some(reply)),
+        Err(ViscaError::SyntaxError | ViscaError::CommandNotExecutableError) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn answer<T: Code>(camera: &mut Camera, inquiry: &impl ViscaInquiry) -> Result<Option<T>> {
+    let Some(reply) = answered(camera.inquire(inquiry))? else { return Ok(None) };
+    Ok(inquiry.parse_value(&reply).and_then(|code| T::from_code(code).ok()))
+}
+
+impl CameraState {
+    /// The commands that bring a camera to this state. Values that only apply
+    /// in a particular mode (iris in manual exposure, R/B gain in manual white
+    /// balance, detail adjustments in manual detail, knee slope and point in
+    /// manual knee, focus position in manual focus, ...) are only sent for that
+    /// mode.
+    pub fn commands(&self) -> Vec<Command> {
+        use commands::{Colour, Detail, Exposure, Focus, Gamma, Knee, PanTilt, Zoom};
+        let mut out: Vec<Command> = Vec::new();
+
+        if let Some(mode) = self.exposure_mode {
+            out.push(exposure_mode(mode).into());
+        }
+        let mode = self.exposure_mode;
+        let manual = |uses: &[ExposureMode]| mode.is_some_and(|m| uses.contains(&m));
+        let automatic = mode.is_some_and(|m| m != ExposureMode::Manual);
+
+        if let Some(iris) = self.iris.filter(|_| manual(&[ExposureMode::Manual, ExposureMode::IrisPri])) {
+            out.push(Exposure::IrisDirect(iris).into());
+        }
+        if let Some(gain) = self.gain.filter(|_| manual(&[ExposureMode::Manual, ExposureMode::GainPri])) {
+            out.push(Exposure::GainDirect(gain).into());
+        }
+        if let Some(shutter) = self.shutter.filter(|_| manual(&[ExposureMode::Manual, ExposureMode::ShutterPri])) {
+            out.push(Exposure::ShutterDirect(shutter).into());
+        }
+        if automatic {
+            out.extend(self.gain_limit.map(|v| Exposure::GainLimit(v).into()));
+            out.extend(self.gain_point.map(|v| Exposure::GainPoint(v).into()));
+            if self.gain_point == Some(OnOff::On) {
+                out.extend(self.gain_point_position.map(|v| Exposure::GainPointPos(v).into()));
+            }
+            out.extend(self.max_shutter.map(|v| Exposure::MaxShutter(v).into()));
+            out.extend(self.min_shutter.map(|v| Exposure::MinShutter(v).into()));
+            out.extend(self.ae_speed.map(|v| Exposure::AESpeed(v).into()));
+            out.extend(self.exp_comp.map(|v| Exposure::ExpCompOnOff(v).into()));
+            if self.exp_comp == Some(OnOff::On) {
+                out.extend(self.exp_comp_level.map(|v| Exposure::ExpCompDirect(v).into()));
+            }
+        }
+        out.extend(self.back_light.map(|v| Exposure::BackLight(v).into()));
+        out.extend(self.spot_light.map(|v| Exposure::SpotLight(v).into()));
+        match self.vis_enhance {
+            Some(OnOff::On) => {
+                out.push(Exposure::VisEnhanceOn.into());
+                out.extend(self.vis_enhance_level.map(|v| {
+                    Exposure::VisEnhanceDirect(v.level, v.brightness_comp, v.comp_level).into()
+                }));
+            }
+            Some(OnOff::Off) => out.push(Exposure::VisEnhanceOff.into()),
+            None => {}
+        }
+        out.extend(self.low_light.map(|v| Exposure::LowLightBasisBrightnessOnOff(v).into()));
+        if self.low_light == Some(OnOff::On) {
+            out.extend(self.low_light_level.map(|v| Exposure::LowLightBasisBrightnessDirect(v).into()));
+        }
+
+        if let Some(mode) = self.wb_mode {
+            out.push(wb_mode(mode).into());
+            if mode == WBMode::Manual {
+                out.extend(self.r_gain.map(|v| Colour::RGainDirect(v).into()));
+                out.extend(self.b_gain.map(|v| Colour::BGainDirect(v).into()));
+            }
+            if matches!(mode, WBMode::Auto1 | WBMode::Auto2) {
+                out.extend(self.wb_speed.map(|v| Colour::Speed(v).into()));
+            }
+        }
+        out.extend(self.chroma_suppress.map(|v| Colour::ChromaSuppress(v).into()));
+        out.extend(self.matrix.map(|v| Colour::MatrixSelect(v).into()));
+        out.extend(self.colour_level.map(|v| Colour::LevelDirect(v).into()));
+        out.extend(self.colour_phase.map(|v| Colour::PhaseDirect(v).into()));
+        out.extend(self.matrix_rg.map(|v| Colour::RG(v).into()));
+        out.extend(self.matrix_rb.map(|v| Colour::RB(v).into()));
+        out.extend(self.matrix_gr.map(|v| Colour::GR(v).into()));
+        out.extend(self.matrix_gb.map(|v| Colour::GB(v).into()));
+        out.extend(self.matrix_br.map(|v| Colour::BR(v).into()));
+        out.extend(self.matrix_bg.map(|v| Colour::BG(v).into()));
+
+        out.extend(self.detail_level.map(|v| Detail::LevelDirect(v).into()));
+        if let Some(mode) = self.detail_mode {
+            out.push(Detail::Mode(mode).into());
+            if mode == DetailMode::Manual {
+                out.extend(self.detail_bandwidth.map(|v| Detail::Bandwidth(v).into()));
+                out.extend(self.detail_crispening.map(|v| Detail::Crispening(v).into()));
+                out.extend(self.detail_hv_balance.map(|v| Detail::HVBalance(v).into()));
+                out.extend(self.detail_bw_balance.map(|v| Detail::BWBalance(v).into()));
+                out.extend(self.detail_limit.map(|v| Detail::Limit(v).into()));
+                out.extend(self.detail_highlight.map(|v| Detail::HighlightedTail(v).into()));
+                out.extend(self.detail_superlow.map(|v| Detail::Superlow(v).into()));
+            }
+        }
+
+        out.extend(self.knee.map(|v| Knee::SettingOnOff(v).into()));
+        if let Some(mode) = self.knee_mode {
+            out.push(Knee::Mode(mode).into());
+            if mode == KneeMode::Manual {
+                out.extend(self.knee_slope.map(|v| Knee::Slope(v).into()));
+                out.extend(self.knee_point.map(|v| Knee::Point(v).into()));
+            }
+        }
+
+        if let Some(mode) = self.gamma_mode {
+            out.push(Gamma::Mode(mode).into());
+            if mode == GammaMode::Pattern {
+                out.extend(self.gamma_pattern.map(|v| Gamma::Pattern(v).into()));
+            }
+        }
+        out.extend(self.gamma_offset.map(|v| Gamma::Offset(v).into()));
+        out.extend(self.gamma_level.map(|v| Gamma::Level(v).into()));
+        out.extend(self.black_gamma_level.map(|v| Gamma::BlackGammaLevel(v).into()));
+        out.extend(self.black_gamma_range.map(|v| Gamma::BlackGammaRange(v).into()));
+        out.extend(self.black_level.map(|v| Gamma::BlackLevelDirect(v).into()));
+
+        if let Some(mode) = self.focus_mode {
+            out.push(match mode {
+                FocusMode::Auto => Focus::Auto.into(),
+                FocusMode::Manual => Focus::Manual.into(),
+            });
+            if mode == FocusMode::Manual {
+                out.extend(self.focus_position.map(|v| Focus::Direct(v).into()));
+            }
+        }
+        out.extend(self.zoom_position.map(|v| Zoom::Direct(v).into()));
+        if let (Some(pan), Some(tilt)) = (self.pan, self.tilt) {
+            out.push(PanTilt::AbsolutePos(PanTiltSpeed::MAX, pan, tilt).into());
+        }
+        out
+    }
+}
+
+impl CameraState {
+    /// The same state without pan/tilt, zoom and focus, leaving only the
+    /// exposure, colour, detail, knee and gamma settings that make cameras
+    /// look alike
+    pub fn picture(&self) -> CameraState {
+        CameraState {
+            focus_mode: None,
+            focus_position: None,
+            zoom_position: None,
+            pan: None,
+            tilt: None,
+            ..self.clone()
+        }
+    }
+
+    /// Only the pan/tilt, zoom and focus of this state
+    pub fn position(&self) -> CameraState {
+        CameraState {
+            focus_mode: self.focus_mode,
+            focus_position: self.focus_position,
+            zoom_position: self.zoom_position,
+            pan: self.pan,
+            tilt: self.tilt,
+            ..CameraState::default()
+        }
+    }
+
+    /// The same state with the shutter speeds moved to the nearest speeds at
+    /// `rate`, for a camera running at another frame rate than this state was
+    /// read at. A state without a frame rate is taken to be at `rate` already.
+    pub fn at_frame_rate(&self, rate: FrameRate) -> CameraState {
+        let Some(from) = self.frame_rate else { return CameraState { frame_rate: Some(rate), ..self.clone() } };
+        let limit = |v: LimitShutter| LimitShutter::clamped(Shutter::from(v).at_rate(from, rate).value());
+        CameraState {
+            shutter: self.shutter.map(|v| v.at_rate(from, rate)),
+            max_shutter: self.max_shutter.map(limit),
+            min_shutter: self.min_shutter.map(limit),
+            frame_rate: Some(rate),
+            ..self.clone()
+        }
+    }
+
+    /// The commands that take a camera in state `other` to this state, i.e.
+    /// those from `commands` that `other` would not already send itself
+    pub fn commands_from(&self, other: &CameraState) -> Vec<Command> {
+        let current = other.commands();
+        self.commands().into_iter().filter(|c| !current.contains(c)).collect()
+    }
+}
+
+// A setting that differs between two states, as the raw values used by
+// `Display`. `None` is a setting that one of the cameras did not report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Difference {
+    pub key: &'static str,
+    pub a: Option<u32>,
+    pub b: Option<u32>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: Option<u32>| v.map_or("-".to_string(), |v| format!("{:X}", v));
+        write!(f, "{}: {} != {}", self.key, show(self.a), show(self.b))
+    }
+}
+
+/// Every setting that is not the same in `a` and `b`, in `Display` order
+pub fn diff(a: &CameraState, b: &CameraState) -> Vec<Difference> {
+    a.entries()
+        .into_iter()
+        .zip(b.entries())
+        .filter(|((_, x), (_, y))| x != y)
+        .map(|((key, a), (_, b))| Difference { key, a, b })
+        .collect()
+}
+
+pub(crate) fn exposure_mode(mode: ExposureMode) -> commands::Exposure {
+    match mode {
+        ExposureMode::FullAuto => commands::Exposure::ModeFullAuto,
+        ExposureMode::Manual => commands::Exposure::ModeManual,
+        ExposureMode::ShutterPri => commands::Exposure::ModeShutterPri,
+        ExposureMode::IrisPri => commands::Exposure::ModeIrisPri,
+        ExposureMode::GainPri => commands::Exposure::ModeGainPri,
+    }
+}
+
+pub(crate) fn wb_mode(mode: WBMode) -> commands::Colour {
+    match mode {
+        WBMode::Auto1 => commands::Colour::WBAuto1,
+        WBMode::Indoor => commands::Colour::WBIndoor,
+        WBMode::Outdoor => commands::Colour::WBOutdoor,
+        WBMode::OnePush => commands::Colour::WBOnePush,
+        WBMode::Auto2 => commands::Colour::WBAuto2,
+        WBMode::Manual => commands::Colour::WBManual,
+    }
+}
+
+impl fmt::Display for CameraState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, code) in self.entries() {
+            if let Some(code) = code {
+                writeln!(f, "{} = {:X}", key, code)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CameraState {
+    type Err = ViscaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut state = CameraState::default();
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or(ViscaError::ParameterOutOfRange("state line"))?;
+            let code = u32::from_str_radix(value.trim(), 16)
+                .map_err(|_| ViscaError::ParameterOutOfRange("state value"))?;
+            state.set_entry(key.trim(), code)?;
+        }
+        Ok(state)
+    }
+}
+
+impl Camera {
+    /// Reads every setting the camera reports into a `CameraState`, along
+    /// with the frame rate from `set_frame_rate`
+    pub fn snapshot(&mut self) -> Result<CameraState> {
+        let state = CameraState::read(self, None)?;
+        Ok(CameraState { frame_rate: self.frame_rate, ..state })
+    }
+
+    /// Brings the camera to `state`, reading the settings `state` has values
+    /// for first and sending only the commands from
+    /// `CameraState::commands_from` that differ, waiting for each to complete.
+    /// Shutter speeds recorded at another frame rate than the camera's
+    /// `set_frame_rate` are converted first.
+    pub fn restore(&mut self, state: &CameraState) -> Result<()> {
+        let state = match (state.frame_rate, self.frame_rate) {
+            (Some(from), Some(to)) if from != to => state.at_frame_rate(to),
+            _ => state.clone(),
+        };
+        // Commands for a setting only depend on settings the state has, so
+        // reading the rest would only slow down e.g. a position recall
+        let current = CameraState::read(self, Some(&state))?;
+        for command in state.commands_from(&current) {
+            let timeout = match command {
+                Command::PanTilt(_) | Command::Zoom(_) | Command::Focus(_) => MOVE_TIMEOUT,
+                _ => self.timeout.unwrap_or(crate::DEFAULT_TIMEOUT),
+            };
+            self.execute(command, timeout)?;
+        }
+        Ok(())
+    }
+}
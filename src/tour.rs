@@ -0,0 +1,266 @@
+// Preset tours ("patrols").
+//
+// A `Tour` visits a list of stops in turn, each a camera memory or a preset
+// from a `PresetStore`, waiting at each for its dwell time. `Tour::start` hands
+// the camera to a background thread that runs the tour and reports progress
+// on a channel; the returned `TourHandle` pauses, resumes and stops it, and
+// gives the camera back when stopped.
+//
+// A camera memory is recalled at its own drive speed. A stop with a speed of
+// its own sets the memory's speed for the recall and puts the stored speed
+// back afterwards, so the memory is left as it was.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::commands::{Command, PanTilt, Preset};
+use crate::inquiry;
+use crate::params::{Code, PanTiltSpeed, PresetNumber};
+use crate::state::MOVE_TIMEOUT;
+use crate::{Camera, PresetStore, Result, ViscaError, ViscaInquiry};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Target {
+    Memory(PresetNumber), // recalled with `Preset::Recall`
+    Stored(String),       // name in the tour's `PresetStore`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TourStop {
+    pub target: Target,
+    pub dwell: Duration,
+    pub speed: Option<PanTiltSpeed>, // pan/tilt speed moving to this stop, the preset's own if None
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tour {
+    pub stops: Vec<TourStop>,
+    pub presets: PresetStore,  // for `Target::Stored` stops
+    pub shuffle: bool,         // visit the stops in a new random order each cycle
+    pub cycles: Option<usize>, // run forever if None
+}
+
+#[derive(Debug)]
+pub enum TourEvent {
+    Moving(usize), // index of the stop in `Tour::stops`
+    Arrived(usize),
+    Failed(usize, ViscaError),
+    CycleComplete(usize), // number of cycles run
+    Paused,
+    Resumed,
+    Finished,
+}
+
+#[derive(Debug, Default)]
+struct Control {
+    paused: bool,
+    stopped: bool,
+    finished: bool, // the tour has ended, on its own or stopped
+}
+
+type Shared = Arc<(Mutex<Control>, Condvar)>;
+
+pub struct TourHandle {
+    control: Shared,
+    events: Receiver<TourEvent>,
+    sender: Sender<TourEvent>,
+    thread: Option<JoinHandle<Camera>>,
+}
+
+impl Tour {
+    /// Runs the tour on a background thread
+    pub fn start(self, camera: Camera) -> TourHandle {
+        let control: Shared = Arc::default();
+        let (sender, events) = mpsc::channel();
+        let shared = Arc::clone(&control);
+        let report = sender.clone();
+        let thread = thread::spawn(move || {
+            let mut camera = camera;
+            self.run(&mut camera, &shared, &report);
+            shared.0.lock().unwrap().finished = true;
+            let _ = report.send(TourEvent::Finished);
+            camera
+        });
+        TourHandle { control, events, sender, thread: Some(thread) }
+    }
+
+    fn run(&self, camera: &mut Camera, control: &Shared, report: &Sender<TourEvent>) {
+        let mut order: Vec<usize> = (0..self.stops.len()).collect();
+        let mut random = seed();
+        let mut cycle = 0;
+        while !order.is_empty() && self.cycles.is_none_or(|cycles| cycle < cycles) {
+            if self.shuffle {
+                shuffle(&mut order, &mut random);
+            }
+            for &index in &order {
+                let stop = &self.stops[index];
+                if !dwell(control, Duration::ZERO) {
+                    return;
+                }
+                let _ = report.send(TourEvent::Moving(index));
+                let _ = match self.visit(camera, stop) {
+                    Ok(()) => report.send(TourEvent::Arrived(index)),
+                    Err(e) => report.send(TourEvent::Failed(index, e)),
+                };
+                if !dwell(control, stop.dwell) {
+                    return;
+                }
+            }
+            cycle += 1;
+            let _ = report.send(TourEvent::CycleComplete(cycle));
+        }
+    }
+
+    fn visit(&self, camera: &mut Camera, stop: &TourStop) -> Result<()> {
+        match &stop.target {
+            Target::Memory(slot) => {
+                let Some(speed) = stop.speed else { return camera.execute(Preset::Recall(*slot), MOVE_TIMEOUT) };
+                // The camera only takes a recall speed as the memory's own
+                // setting, so the stored one is put back afterwards
+                let inquiry = inquiry::Preset::DriveSpeed(*slot);
+                let reply = camera.inquire(&inquiry)?;
+                let stored = inquiry
+                    .parse_value(&reply)
+                    .and_then(|v| PanTiltSpeed::from_code(v).ok())
+                    .ok_or(ViscaError::UnexpectedReply(reply))?;
+                camera.execute(Preset::PresetDriveSpeed(*slot, speed), MOVE_TIMEOUT)?;
+                let recalled = camera.execute(Preset::Recall(*slot), MOVE_TIMEOUT);
+                let restored = camera.execute(Preset::PresetDriveSpeed(*slot, stored), MOVE_TIMEOUT);
+                recalled.and(restored)
+            }
+            Target::Stored(name) => {
+                let state = self.presets.get(name).ok_or(ViscaError::ParameterOutOfRange("preset name"))?;
+                for command in state.commands() {
+                    let command = match (command, stop.speed) {
+                        (Command::PanTilt(PanTilt::AbsolutePos(_, p, t)), Some(speed)) => {
+                            PanTilt::AbsolutePos(speed, p, t).into()
+                        }
+                        (command, _) => command,
+                    };
+                    camera.execute(command, MOVE_TIMEOUT)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl TourHandle {
+    /// Progress of the tour. Ends with `TourEvent::Finished`.
+    pub fn events(&self) -> &Receiver<TourEvent> {
+        &self.events
+    }
+
+    /// Holds the tour once the current move is done. Does nothing once the
+    /// tour has ended.
+    pub fn pause(&self) {
+        self.set_paused(true, TourEvent::Paused);
+    }
+
+    /// Continues a paused tour, with what was left of the dwell time
+    pub fn resume(&self) {
+        self.set_paused(false, TourEvent::Resumed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.0.lock().unwrap().paused
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Ends the tour, waiting for any move in progress to complete, and
+    /// returns the camera
+    pub fn stop(mut self) -> Option<Camera> {
+        self.finish()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Control)) {
+        let (lock, condvar) = &*self.control;
+        change(&mut lock.lock().unwrap());
+        condvar.notify_one();
+    }
+
+    // Checked and reported under the lock the tour thread takes to finish,
+    // so the event can't follow `TourEvent::Finished`
+    fn set_paused(&self, paused: bool, event: TourEvent) {
+        let (lock, condvar) = &*self.control;
+        let mut control = lock.lock().unwrap();
+        if control.stopped || control.finished {
+            return;
+        }
+        control.paused = paused;
+        condvar.notify_one();
+        let _ = self.sender.send(event);
+    }
+
+    fn finish(&mut self) -> Option<Camera> {
+        self.update(|c| c.stopped = true);
+        self.thread.take().and_then(|thread| thread.join().ok())
+    }
+}
+
+impl Drop for TourHandle {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+// Waits out `time` while the tour is running, and for as long as it is
+// paused. Returns false once the tour has been stopped.
+fn dwell(control: &Shared, mut time: Duration) -> bool {
+    let (lock, condvar) = &**control;
+    let mut state = lock.lock().unwrap();
+    loop {
+        if state.stopped {
+            return false;
+        }
+        if state.paused {
+            state = condvar.wait(state).unwrap();
+            continue;
+        }
+        if time.is_zero() {
+            return true;
+        }
+        let start = Instant::now();
+        state = condvar.wait_timeout(state, time).unwrap().0;
+        time = time.saturating_sub(start.elapsed());
+    }
+}
+
+// xorshift, plenty for picking an order to visit presets in
+fn seed() -> u64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |t| t.as_nanos() as u64);
+    nanos | 1
+}
+
+fn shuffle(order: &mut [usize], state: &mut u64) {
+    for i in (1..order.len()).rev() {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        order.swap(i, (*state % (i as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_pause_after_finish() {
+        let handle = Tour::default().start(Camera::new("127.0.0.1:9").unwrap());
+        let events = handle.events();
+        assert!(matches!(events.recv_timeout(Duration::from_secs(5)), Ok(TourEvent::Finished)));
+        handle.pause();
+        handle.resume();
+        assert!(!handle.is_paused());
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+// Dead-man watchdog: stops the camera if movement updates go quiet.
+//
+// A background thread waits on the last time the operator was heard from. When a
+// continuous movement has been sent and nothing follows within the interval, it
+// sends pan/tilt, zoom and focus stops through its own handle on the camera socket.
+
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::commands::{Focus, PanTilt, Zoom};
+use crate::messages::ViscaMessage;
+use crate::params::PanTiltSpeed;
+
+pub fn stop_bytes() -> [Vec<u8>; 3] {
+    let speed = PanTiltSpeed::MIN;
+    [PanTilt::Stop(speed, speed).bytes(), Zoom::Stop.bytes(), Focus::Stop.bytes()]
+}
+
+#[derive(Debug)]
+struct State {
+    last_feed: Instant,
+    armed: bool, // a movement is in progress as far as we know
+    shutdown: bool,
+}
+
+#[derive(Debug)]
+pub struct Watchdog {
+    state: Arc<(Mutex<State>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub fn spawn<F>(interval: Duration, mut stop: F) -> Self
+    where
+        F: FnMut() -> io::Result<()> + Send + 'static,
+    {
+        let state = Arc::new((
+            Mutex::new(State { last_feed: Instant::now(), armed: false, shutdown: false }),
+            Condvar::new(),
+        ));
+        let shared = Arc::clone(&state);
+
+        let thread = thread::spawn(move || {
+            let (lock, condvar) = &*shared;
+            let mut state = lock.lock().unwrap();
+            loop {
+                if state.shutdown {
+                    return;
+                }
+                let wait = if state.armed {
+                    let silent = state.last_feed.elapsed();
+                    if silent >= interval {
+                        // Disarm even if sending fails, there is nobody to report to
+                        // and retrying every tick would flood a camera that is down
+                        state.armed = false;
+                        let _ = stop();
+                        continue;
+                    }
+                    interval - silent
+                } else {
+                    interval
+                };
+                state = condvar.wait_timeout(state, wait).unwrap().0;
+            }
+        });
+
+        Watchdog { state, thread: Some(thread) }
+    }
+
+    pub fn feed(&self) {
+        let (lock, _) = &*self.state;
+        lock.lock().unwrap().last_feed = Instant::now();
+    }
+
+    pub fn arm(&self) {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock().unwrap();
+        state.last_feed = Instant::now();
+        state.armed = true;
+        condvar.notify_one();
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.state;
+        lock.lock().unwrap().shutdown = true;
+        condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}